@@ -0,0 +1,328 @@
+use std::fmt;
+use rust_decimal::{Decimal, MathematicalOps};
+
+use crate::units::currency::Currency;
+use crate::units::error::{UnitError, checked_decimal};
+use crate::units::money::Money;
+use crate::units::percentage::Percentage;
+use crate::units::rounding::RoundingPolicy;
+
+/*
+    Financial math
+
+    Rates are per period: a 6% yearly loan paid monthly is 0.5% over
+    360 periods. Results in Money are rounded to the minor unit of
+    their currency, intermediate values are not.
+    Nothing panics: a rate that would divide by zero (-100%) is an
+    InvalidRate, results too large for a Decimal an Overflow.
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FinanceError {
+    NoCashFlows,
+    MixedCurrencies,
+    NoPeriods,
+    // IRR: cash flows without both signs or that don't converge
+    NoSolution,
+    // NaN, infinite, or -100% where the result would divide by zero
+    InvalidRate,
+    Overflow
+}
+
+impl fmt::Display for FinanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinanceError::NoCashFlows => write!(f, "no cash flows"),
+            FinanceError::MixedCurrencies => write!(f, "cash flows in different currencies"),
+            FinanceError::NoPeriods => write!(f, "number of periods must be positive"),
+            FinanceError::NoSolution => write!(f, "no internal rate of return found"),
+            FinanceError::InvalidRate => write!(f, "invalid rate"),
+            FinanceError::Overflow => write!(f, "overflow"),
+        }
+    }
+}
+
+impl std::error::Error for FinanceError {}
+
+// From the checked Money arithmetic, amounts here share their currency
+impl From<UnitError> for FinanceError {
+    fn from(e: UnitError) -> Self {
+        match e {
+            UnitError::IncompatibleUnits(_) => FinanceError::MixedCurrencies,
+            UnitError::DivisionByZero | UnitError::NaN => FinanceError::InvalidRate,
+            _ => FinanceError::Overflow,
+        }
+    }
+}
+
+// 5% -> 0.05
+fn rate_of(rate: Percentage) -> Result<Decimal, FinanceError> {
+    let rate = checked_decimal(rate.value).map_err(|_| FinanceError::InvalidRate)?;
+    Ok(rate / Decimal::ONE_HUNDRED)
+}
+
+// (1 + rate)^periods
+fn growth(rate: Percentage, periods: u32) -> Result<Decimal, FinanceError> {
+    (Decimal::ONE + rate_of(rate)?).checked_powu(u64::from(periods)).ok_or(FinanceError::Overflow)
+}
+
+fn sub(a: Decimal, b: Decimal) -> Result<Decimal, FinanceError> {
+    a.checked_sub(b).ok_or(FinanceError::Overflow)
+}
+
+fn mul(a: Decimal, b: Decimal) -> Result<Decimal, FinanceError> {
+    a.checked_mul(b).ok_or(FinanceError::Overflow)
+}
+
+// Only called with a divisor that depends on the rate
+fn div(a: Decimal, b: Decimal) -> Result<Decimal, FinanceError> {
+    if b.is_zero() {
+        return Err(FinanceError::InvalidRate);
+    }
+    a.checked_div(b).ok_or(FinanceError::Overflow)
+}
+
+fn rounded(amount: Decimal, currency: Currency) -> Money {
+    Money::new(amount, currency).round(RoundingPolicy::default())
+}
+
+// Interest on `principal` alone: 1000€ at 5% for 3 periods = 150€
+pub fn simple_interest(principal: Money, rate: Percentage, periods: Decimal) -> Result<Money, FinanceError> {
+    let interest = mul(mul(principal.amount, rate_of(rate)?)?, periods)?;
+    Ok(rounded(interest, principal.currency))
+}
+
+// Interest on the principal and the interest of previous periods
+pub fn compound_interest(principal: Money, rate: Percentage, periods: u32) -> Result<Money, FinanceError> {
+    let interest = mul(principal.amount, sub(growth(rate, periods)?, Decimal::ONE)?)?;
+    Ok(rounded(interest, principal.currency))
+}
+
+// What `present` is worth after `periods`: 1000€ at 5% for 10 periods = 1628.89€
+pub fn future_value(present: Money, rate: Percentage, periods: u32) -> Result<Money, FinanceError> {
+    Ok(rounded(mul(present.amount, growth(rate, periods)?)?, present.currency))
+}
+
+// What `future` is worth today: 1000€ in 10 periods at 5% = 613.91€
+pub fn present_value(future: Money, rate: Percentage, periods: u32) -> Result<Money, FinanceError> {
+    Ok(rounded(div(future.amount, growth(rate, periods)?)?, future.currency))
+}
+
+// Constant payment paying off `principal` in `periods`, interest included
+pub fn annuity_payment(principal: Money, rate: Percentage, periods: u32) -> Result<Money, FinanceError> {
+    if periods == 0 {
+        return Err(FinanceError::NoPeriods);
+    }
+
+    let r = rate_of(rate)?;
+    let payment = if r.is_zero() {
+        principal.amount / Decimal::from(periods)
+    } else {
+        div(mul(principal.amount, r)?, sub(Decimal::ONE, div(Decimal::ONE, growth(rate, periods)?)?)?)?
+    };
+
+    Ok(rounded(payment, principal.currency))
+}
+
+// Currency shared by all cash flows
+fn currency_of(cash_flows: &[Money]) -> Result<Currency, FinanceError> {
+    let currency = cash_flows.first().ok_or(FinanceError::NoCashFlows)?.currency;
+    if cash_flows.iter().any(|cash_flow| cash_flow.currency != currency) {
+        return Err(FinanceError::MixedCurrencies);
+    }
+    Ok(currency)
+}
+
+/*
+    Net present value
+
+    The first cash flow is today (not discounted), the next one
+    a period later and so on: 10%, [-1000€, 300€, 400€, 500€] = -21.04€
+*/
+pub fn npv(rate: Percentage, cash_flows: &[Money]) -> Result<Money, FinanceError> {
+    let currency = currency_of(cash_flows)?;
+    let discount = Decimal::ONE + rate_of(rate)?;
+
+    let mut total = Decimal::ZERO;
+    let mut factor = Decimal::ONE;
+    for cash_flow in cash_flows {
+        total = total.checked_add(div(cash_flow.amount, factor)?).ok_or(FinanceError::Overflow)?;
+        factor = mul(factor, discount)?;
+    }
+
+    Ok(rounded(total, currency))
+}
+
+/*
+    Internal rate of return
+
+    The rate for which the NPV of the cash flows is zero.
+    Newton's method from 10%, bisection when it doesn't converge.
+*/
+pub fn irr(cash_flows: &[Money]) -> Result<Percentage, FinanceError> {
+    currency_of(cash_flows)?;
+
+    let flows: Vec<f64> = cash_flows.iter().map(|&cash_flow| f64::from(cash_flow)).collect();
+    if !(flows.iter().any(|&f| f > 0.0) && flows.iter().any(|&f| f < 0.0)) {
+        return Err(FinanceError::NoSolution);
+    }
+
+    let npv = |r: f64| flows.iter().enumerate().map(|(t, f)| f / (1.0 + r).powi(t as i32)).sum::<f64>();
+    let derivative = |r: f64| {
+        flows.iter().enumerate().map(|(t, f)| -(t as f64) * f / (1.0 + r).powi(t as i32 + 1)).sum::<f64>()
+    };
+
+    let mut r = 0.1;
+    for _ in 0..100 {
+        let step = npv(r) / derivative(r);
+        if !step.is_finite() || r - step <= -1.0 {
+            break;
+        }
+        r -= step;
+        if step.abs() < 1e-12 {
+            return Ok(Percentage::new(r * 100.0));
+        }
+    }
+
+    // npv is monotonic enough between -99.99% and 10000% for sane cash flows
+    let (mut low, mut high) = (-0.9999, 100.0);
+    if npv(low).signum() == npv(high).signum() {
+        return Err(FinanceError::NoSolution);
+    }
+
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if npv(mid).signum() == npv(low).signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(Percentage::new((low + high) / 2.0 * 100.0))
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct AmortizationRow {
+    // starts at 1
+    pub period: u32,
+    pub payment: Money,
+    pub interest: Money,
+    pub principal: Money,
+    // left to pay after this period
+    pub balance: Money
+}
+
+/*
+    Loan amortization schedule
+
+    Constant payments, the interest is rounded every period and the
+    last payment is adjusted so the balance ends at exactly zero.
+*/
+pub fn amortization_schedule(principal: Money, rate: Percentage, periods: u32) -> Result<Vec<AmortizationRow>, FinanceError> {
+    let payment = annuity_payment(principal, rate, periods)?;
+    let r = rate_of(rate)?;
+    let mut balance = principal;
+
+    (1..=periods).map(|period| {
+        let interest = rounded(mul(balance.amount, r)?, balance.currency);
+        let repaid = if period == periods { balance } else { payment.checked_sub(interest)? };
+        balance = balance.checked_sub(repaid)?;
+
+        Ok(AmortizationRow { period, payment: repaid.checked_add(interest)?, interest, principal: repaid, balance })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::eur;
+
+    #[test]
+    fn interest() {
+        assert_eq!(simple_interest(eur(dec!(1000)), Percentage::new(5.0), dec!(3)), Ok(eur(dec!(150))));
+        assert_eq!(compound_interest(eur(dec!(1000)), Percentage::new(5.0), 10), Ok(eur(dec!(628.89))));
+        assert_eq!(future_value(eur(dec!(1000)), Percentage::new(5.0), 10), Ok(eur(dec!(1628.89))));
+        assert_eq!(present_value(eur(dec!(1000)), Percentage::new(5.0), 10), Ok(eur(dec!(613.91))));
+    }
+
+    #[test]
+    fn total_loss() {
+        let all = Percentage::new(-100.0);
+        assert_eq!(future_value(eur(dec!(1000)), all, 3), Ok(eur(dec!(0))));
+        assert_eq!(present_value(eur(dec!(1000)), all, 3), Err(FinanceError::InvalidRate));
+        assert_eq!(annuity_payment(eur(dec!(1000)), all, 12), Err(FinanceError::InvalidRate));
+        assert_eq!(npv(all, &[eur(dec!(-100)), eur(dec!(50))]), Err(FinanceError::InvalidRate));
+        assert_eq!(future_value(eur(dec!(1)), Percentage::new(f64::NAN), 1), Err(FinanceError::InvalidRate));
+    }
+
+    #[test]
+    fn overflow() {
+        let rate = Percentage::new(5.0);
+        assert_eq!(future_value(eur(dec!(1000)), rate, u32::MAX), Err(FinanceError::Overflow));
+        assert_eq!(compound_interest(eur(dec!(1000)), rate, 100_000), Err(FinanceError::Overflow));
+        assert_eq!(present_value(eur(dec!(1000)), rate, 100_000), Err(FinanceError::Overflow));
+        assert_eq!(annuity_payment(eur(dec!(1000)), Percentage::new(1e20), 12), Err(FinanceError::Overflow));
+        assert_eq!(simple_interest(Money::new(Decimal::MAX, Currency::EUR), rate, dec!(30)), Err(FinanceError::Overflow));
+
+        // the payment fits, paying it and the negative interest does not
+        let loan = eur(dec!(9e27));
+        assert!(annuity_payment(loan, Percentage::new(-190.0), 2).is_ok());
+        assert_eq!(amortization_schedule(loan, Percentage::new(-190.0), 2), Err(FinanceError::Overflow));
+    }
+
+    #[test]
+    fn annuity() {
+        // 30 year mortgage at 6%, paid monthly
+        let usd = Money::new(dec!(200000), Currency::USD);
+        assert_eq!(annuity_payment(usd, Percentage::new(0.5), 360), Ok(Money::new(dec!(1199.10), Currency::USD)));
+
+        assert_eq!(annuity_payment(eur(dec!(1200)), Percentage::new(0.0), 12), Ok(eur(dec!(100))));
+        assert_eq!(annuity_payment(eur(dec!(1200)), Percentage::new(1.0), 0), Err(FinanceError::NoPeriods));
+    }
+
+    #[test]
+    fn net_present_value() {
+        let flows = [eur(dec!(-1000)), eur(dec!(300)), eur(dec!(400)), eur(dec!(500))];
+        assert_eq!(npv(Percentage::new(10.0), &flows), Ok(eur(dec!(-21.04))));
+
+        assert_eq!(npv(Percentage::new(10.0), &[]), Err(FinanceError::NoCashFlows));
+        assert_eq!(
+            npv(Percentage::new(10.0), &[eur(dec!(-1)), Money::new(dec!(1), Currency::USD)]),
+            Err(FinanceError::MixedCurrencies)
+        );
+    }
+
+    #[test]
+    fn internal_rate_of_return() {
+        let flows = [eur(dec!(-100)), eur(dec!(39)), eur(dec!(59)), eur(dec!(55)), eur(dec!(20))];
+        let rate = irr(&flows).unwrap();
+        assert!((rate.value - 28.0948).abs() < 1e-4, "{}", rate.value);
+
+        // the IRR makes the NPV zero
+        assert_eq!(npv(rate, &flows), Ok(eur(dec!(0))));
+
+        assert_eq!(irr(&[eur(dec!(100)), eur(dec!(50))]), Err(FinanceError::NoSolution));
+    }
+
+    #[test]
+    fn amortization() {
+        let schedule = amortization_schedule(eur(dec!(1000)), Percentage::new(1.0), 12).unwrap();
+        assert_eq!(schedule.len(), 12);
+
+        assert_eq!(schedule[0], AmortizationRow {
+            period: 1,
+            payment: eur(dec!(88.85)),
+            interest: eur(dec!(10.00)),
+            principal: eur(dec!(78.85)),
+            balance: eur(dec!(921.15))
+        });
+
+        let last = schedule[11];
+        assert_eq!(last.balance, eur(dec!(0)));
+        assert!((last.payment - eur(dec!(88.85))).amount.abs() <= dec!(0.05));
+
+        let repaid = schedule.iter().fold(eur(dec!(0)), |total, row| total + row.principal);
+        assert_eq!(repaid, eur(dec!(1000)));
+    }
+}
@@ -0,0 +1,700 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::NaiveDate;
+
+use super::{AccountKind, Ledger, LedgerError, Posting, Transaction};
+use crate::units::format::Locale;
+use crate::units::money::Money;
+use crate::units::parse::MoneyParser;
+
+/*
+    Plain text journal
+
+    The common subset of ledger-cli and beancount:
+
+    ; comment
+    2024-01-01 open Assets:Bank
+    2024-01-01 * "Bank" "Opening balance"
+        Assets:Bank         1000.00 EUR
+        Equity:Capital
+    2024-01-31 balance Assets:Bank  400.00 EUR
+
+    Dates are written 2024-01-01 or 2024/01/01. A transaction has a flag
+    (*, ! or txn) and a quoted payee and narration as in beancount, or
+    a plain description as in ledger-cli.
+
+    Postings are indented, the account and the amount are separated by
+    two spaces or a tab (account names may contain single spaces).
+    One posting per transaction may leave its amount out, it gets
+    whatever balances the transaction. Indented `key: value` metadata
+    lines are skipped.
+
+    `balance` asserts the balance of an account in one currency at the
+    start of the day, before the transactions of that day, as beancount
+    does. Accounts are opened on first use when their kind shows in the
+    name (Assets, Liabilities, Equity, Income, Expenses).
+
+    Other directives (close, price, option...) are not read, they are
+    kept as written like comments. Entries are written back the way they
+    were read, amounts and comments included, as long as they are not
+    changed. A changed entry is written with the code of each currency
+    and the precision of each amount: 1000.00 EUR.
+*/
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Entry {
+    Open { date: NaiveDate, account: String, kind: AccountKind },
+    // flag is * (cleared) or ! (pending), the narration is the description
+    Transaction { flag: Option<char>, payee: Option<String>, transaction: Transaction },
+    Balance { date: NaiveDate, account: String, amount: Money },
+    // Comment lines
+    Comment(String),
+    // A directive that is not read and the lines under it
+    Directive(String)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    pub entries: Vec<Entry>,
+    // Each entry as it was read and its lines
+    source: Vec<(Entry, String)>
+}
+
+// Journals with the same entries are equal, however they were written
+impl PartialEq for Journal {
+    fn eq(&self, other: &Journal) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for Journal {}
+
+// Lines and columns start at 1
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct JournalError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for JournalError {}
+
+impl Journal {
+    pub fn parse(s: &str) -> Result<Journal, JournalError> {
+        let mut parser = Parser::default();
+
+        for (i, raw) in s.lines().enumerate() {
+            parser.line(i + 1, raw)?;
+        }
+        parser.finish()?;
+
+        Ok(Journal { entries: parser.entries, source: parser.source })
+    }
+
+    // Replay the journal in a ledger
+    pub fn to_ledger(&self) -> Result<Ledger, LedgerError> {
+        let mut ledger = Ledger::new();
+
+        for entry in &self.entries {
+            match entry {
+                Entry::Open { account, kind, .. } => ledger.open(account, *kind)?,
+                Entry::Transaction { transaction, .. } => {
+                    for posting in &transaction.postings {
+                        if let (None, Some(kind)) = (ledger.account(&posting.account), kind_of(&posting.account)) {
+                            ledger.open(&posting.account, kind)?;
+                        }
+                    }
+                    ledger.add(transaction.clone())?;
+                },
+                Entry::Balance { .. } | Entry::Comment(_) | Entry::Directive(_) => {},
+            }
+        }
+
+        Ok(ledger)
+    }
+}
+
+impl FromStr for Journal {
+    type Err = JournalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Journal::parse(s)
+    }
+}
+
+// Undated directives of beancount and ledger-cli
+const UNDATED: [&str; 25] = [
+    "option", "plugin", "include", "pushtag", "poptag", "pushmeta", "popmeta",
+    "account", "alias", "apply", "end", "commodity", "payee", "tag", "year", "bucket", "define",
+    "P", "D", "N", "Y", "A", "C", "~", "="
+];
+
+// Dated directives of beancount, besides open and balance
+const DATED: [&str; 9] = ["close", "commodity", "price", "pad", "note", "document", "event", "query", "custom"];
+
+// Posting without amount: line, account, position in the transaction
+type Elided = (usize, String, usize);
+
+// What the lines being read make
+enum Block {
+    // open or balance, checked already
+    Entry(Entry),
+    // header line, flag, payee, postings so far and the one without amount
+    Transaction(usize, Option<char>, Option<String>, Transaction, Option<Elided>),
+    Comment,
+    Directive
+}
+
+#[derive(Default)]
+struct Parser {
+    entries: Vec<Entry>,
+    source: Vec<(Entry, String)>,
+    ledger: Ledger,
+    // entry being read and its lines so far
+    current: Option<(Block, String)>
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, raw: &str) -> Result<(), JournalError> {
+        let error = |column: usize, message: String| JournalError { line: number, column, message };
+
+        if raw.trim().is_empty() {
+            return Ok(());
+        }
+
+        let content = strip_comment(raw).trim_end();
+        if raw.starts_with([' ', '\t']) {
+            return self.indented(number, raw, content.trim());
+        }
+
+        self.finish()?;
+
+        if raw.starts_with([';', '#', '%', '|', '*']) {
+            self.current = Some((Block::Comment, raw.to_owned()));
+            return Ok(());
+        }
+
+        let (first, rest) = content.split_once(char::is_whitespace).unwrap_or((content, ""));
+        if UNDATED.contains(&first) {
+            self.current = Some((Block::Directive, raw.to_owned()));
+            return Ok(());
+        }
+
+        let date = parse_date(first).ok_or_else(|| error(1, format!("invalid date {:?}", first)))?;
+        let rest = rest.trim();
+
+        let (keyword, arguments) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let arguments_column = column_of(raw, arguments.trim_start());
+        let arguments = arguments.trim();
+
+        let block = match keyword {
+            "open" => {
+                // beancount currency constraints after the account are ignored
+                let account = arguments.split_whitespace().next()
+                    .ok_or_else(|| error(arguments_column, "missing account".to_owned()))?;
+                let kind = kind_of(account)
+                    .ok_or_else(|| error(arguments_column, format!("can't tell the kind of account {:?}", account)))?;
+
+                self.ledger.open(account, kind).map_err(|e| error(arguments_column, e.to_string()))?;
+                Block::Entry(Entry::Open { date, account: account.to_owned(), kind })
+            },
+            "balance" => {
+                let (account, amount) = split_posting(arguments);
+                if amount.is_empty() {
+                    return Err(error(arguments_column, "missing amount".to_owned()));
+                }
+
+                let amount_column = column_of(raw, amount);
+                let amount = parse_amount(amount).map_err(|message| error(amount_column, message))?;
+                self.ensure_account(account).map_err(|message| error(arguments_column, message))?;
+
+                // The balance at the end of the day before
+                let balance = date.pred_opt()
+                    .map(|day| self.ledger.balance_at(account, day))
+                    .unwrap_or_default()
+                    .get(amount.currency);
+                if balance.amount != amount.amount {
+                    return Err(error(amount_column, format!(
+                        "balance assertion failed: {} is {}, expected {}", account, write_amount(balance), write_amount(amount)
+                    )));
+                }
+
+                Block::Entry(Entry::Balance { date, account: account.to_owned(), amount })
+            },
+            keyword if DATED.contains(&keyword) => Block::Directive,
+            _ => {
+                let (flag, payee, description) = header(rest).map_err(|(part, message)| error(column_of(raw, part), message))?;
+                Block::Transaction(number, flag, payee, Transaction::new(date, &description), None)
+            },
+        };
+
+        self.current = Some((block, raw.to_owned()));
+        Ok(())
+    }
+
+    // Postings, metadata and comments under an entry
+    fn indented(&mut self, number: usize, raw: &str, content: &str) -> Result<(), JournalError> {
+        match &mut self.current {
+            Some((block, lines)) if matches!(block, Block::Directive) || content.is_empty() || is_metadata(content) => {
+                lines.push('\n');
+                lines.push_str(raw);
+                Ok(())
+            },
+            None if content.is_empty() => {
+                self.current = Some((Block::Comment, raw.to_owned()));
+                Ok(())
+            },
+            _ => self.posting(number, raw, content),
+        }
+    }
+
+    fn posting(&mut self, number: usize, raw: &str, content: &str) -> Result<(), JournalError> {
+        let column = column_of(raw, content);
+        let error = |column: usize, message: String| JournalError { line: number, column, message };
+
+        let (account, amount) = split_posting(content);
+        let amount_column = column_of(raw, amount);
+
+        self.ensure_account(account).map_err(|message| error(column, message))?;
+
+        let Some((Block::Transaction(_, _, _, transaction, elided), lines)) = self.current.as_mut() else {
+            return Err(error(column, "posting outside of a transaction".to_owned()));
+        };
+
+        if amount.is_empty() {
+            if elided.is_some() {
+                return Err(error(column, "only one posting can leave its amount out".to_owned()));
+            }
+            *elided = Some((number, account.to_owned(), transaction.postings.len()));
+        } else {
+            let amount = parse_amount(amount).map_err(|message| error(amount_column, message))?;
+            transaction.postings.push(Posting { account: account.to_owned(), amount });
+        }
+
+        lines.push('\n');
+        lines.push_str(raw);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), JournalError> {
+        let Some((block, lines)) = self.current.take() else {
+            return Ok(());
+        };
+
+        let entry = match block {
+            Block::Entry(entry) => entry,
+            Block::Transaction(number, flag, payee, transaction, elided) => {
+                let transaction = self.transaction(number, transaction, elided)?;
+                Entry::Transaction { flag, payee, transaction }
+            },
+            Block::Comment => Entry::Comment(lines.clone()),
+            Block::Directive => Entry::Directive(lines.clone()),
+        };
+
+        self.source.push((entry.clone(), lines));
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    // Fill in the posting without amount and add the transaction to the ledger
+    fn transaction(&mut self, number: usize, mut transaction: Transaction, elided: Option<Elided>) -> Result<Transaction, JournalError> {
+        if let Some((line, account, index)) = elided {
+            let remainder = transaction.remainder();
+            let mut missing = remainder.iter();
+
+            let amount = match (missing.next(), missing.next()) {
+                (Some(amount), None) => Money::new(-amount.amount, amount.currency),
+                _ => return Err(JournalError {
+                    line,
+                    column: 1,
+                    message: format!("can't infer the amount of {}, {} left over", account, remainder)
+                }),
+            };
+
+            transaction.postings.insert(index, Posting { account, amount });
+        }
+
+        self.ledger.add(transaction.clone())
+            .map_err(|e| JournalError { line: number, column: 1, message: e.to_string() })?;
+        Ok(transaction)
+    }
+
+    // Open accounts on first use
+    fn ensure_account(&mut self, account: &str) -> Result<(), String> {
+        if account.is_empty() {
+            return Err("missing account".to_owned());
+        }
+
+        if self.ledger.account(account).is_none() {
+            let kind = kind_of(account).ok_or_else(|| format!("unknown account {:?}", account))?;
+            self.ledger.open(account, kind).map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+// The line without its ; comment, a ; between quotes is text
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' if !escaped => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            _ => {},
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    line
+}
+
+// 2024-01-01 or 2024/01/01
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y/%m/%d"))
+        .ok()
+}
+
+// Flag, payee and narration or description
+type Header = (Option<char>, Option<String>, String);
+
+// `* "Payee" "Narration"` or `* Rent`, on error the part of `s` that is wrong
+fn header(s: &str) -> Result<Header, (&str, String)> {
+    let txn = s.strip_prefix("txn").filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+
+    let (flag, s) = match (s.chars().next(), txn) {
+        (Some(flag @ ('*' | '!')), _) => (Some(flag), s[1..].trim_start()),
+        (_, Some(rest)) => (Some('*'), rest.trim_start()),
+        _ => (None, s),
+    };
+
+    if !s.starts_with('"') {
+        return Ok((flag, None, s.to_owned()));
+    }
+
+    let mut strings = Vec::new();
+    let mut rest = s;
+    while let Some(quoted) = rest.strip_prefix('"') {
+        let (string, after) = unquote(quoted).ok_or((rest, "unterminated string".to_owned()))?;
+        strings.push(string);
+        rest = after.trim_start();
+    }
+
+    // beancount tags and links, kept in the source only
+    if let Some(word) = rest.split_whitespace().find(|word| !word.starts_with(['#', '^'])) {
+        return Err((word, format!("unexpected {:?} after the narration", word)));
+    }
+
+    match <[String; 2]>::try_from(strings) {
+        Ok([payee, narration]) => Ok((flag, Some(payee), narration)),
+        Err(mut strings) if strings.len() == 1 => Ok((flag, None, strings.remove(0))),
+        Err(_) => Err((s, "expected a payee and a narration".to_owned())),
+    }
+}
+
+// The string up to the closing quote and what follows it, \" and \\ are escapes
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let mut string = String::new();
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, &s[i + 1..])),
+            '\\' => string.push(chars.next()?.1),
+            _ => string.push(c),
+        }
+    }
+
+    None
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// beancount metadata: keys start with a lowercase letter, accounts with an uppercase one
+fn is_metadata(s: &str) -> bool {
+    let Some((key, value)) = s.split_once(':') else {
+        return false;
+    };
+
+    key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && (value.is_empty() || value.starts_with(char::is_whitespace))
+}
+
+// "Assets:Bank  1000.00 EUR" -> ("Assets:Bank", "1000.00 EUR")
+fn split_posting(s: &str) -> (&str, &str) {
+    let split = s.find("  ").or_else(|| s.find('\t')).or_else(|| s.find(' '));
+
+    match split {
+        Some(i) => (s[..i].trim(), s[i..].trim()),
+        None => (s, ""),
+    }
+}
+
+// Always a decimal point, 1.459 EUR is not 1459 EUR
+fn parse_amount(s: &str) -> Result<Money, String> {
+    MoneyParser::new().with_locale(Locale::EN_US).parse(s).map_err(|e| format!("{} in {:?}", e, s))
+}
+
+// Column of `part`, a slice of `line`
+fn column_of(line: &str, part: &str) -> usize {
+    let offset = (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize).min(line.len());
+    line[..offset].chars().count() + 1
+}
+
+fn kind_of(account: &str) -> Option<AccountKind> {
+    match account.split(':').next()? {
+        "Assets" | "Asset" => Some(AccountKind::Asset),
+        "Liabilities" | "Liability" => Some(AccountKind::Liability),
+        "Equity" => Some(AccountKind::Equity),
+        "Income" | "Revenue" | "Revenues" => Some(AccountKind::Income),
+        "Expenses" | "Expense" => Some(AccountKind::Expense),
+        _ => None,
+    }
+}
+
+// 1000.00 EUR, with the precision of the amount
+fn write_amount(money: Money) -> String {
+    format!("{} {}", money.amount, money.currency.code)
+}
+
+fn write_entry(f: &mut fmt::Formatter<'_>, entry: &Entry) -> fmt::Result {
+    match entry {
+        Entry::Open { date, account, .. } => writeln!(f, "{} open {}", date, account),
+        Entry::Balance { date, account, amount } => {
+            writeln!(f, "{} balance {}  {}", date, account, write_amount(*amount))
+        },
+        Entry::Transaction { flag, payee, transaction } => {
+            write!(f, "{}", transaction.date)?;
+            if let Some(flag) = flag {
+                write!(f, " {}", flag)?;
+            }
+
+            let description = &transaction.description;
+            match payee {
+                Some(payee) => writeln!(f, " {} {}", quote(payee), quote(description))?,
+                None if description.starts_with('"') || description.contains(';') => writeln!(f, " {}", quote(description))?,
+                None => writeln!(f, " {}", description)?,
+            }
+
+            let width = transaction.postings.iter().map(|p| p.account.chars().count()).max().unwrap_or(0);
+            for posting in &transaction.postings {
+                writeln!(f, "    {:<width$}  {:>12}", posting.account, write_amount(posting.amount))?;
+            }
+
+            Ok(())
+        },
+        Entry::Comment(lines) | Entry::Directive(lines) => writeln!(f, "{}", lines),
+    }
+}
+
+impl fmt::Display for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            let is_transaction = matches!(entry, Entry::Transaction { .. });
+            let previous = i.checked_sub(1).map(|i| &self.entries[i]);
+            let separate = match previous {
+                Some(Entry::Transaction { .. }) => true,
+                // a comment right above a transaction stays with it
+                Some(Entry::Comment(_)) | None => false,
+                Some(_) => is_transaction,
+            };
+            if separate {
+                writeln!(f)?;
+            }
+
+            match self.source.get(i) {
+                Some((read, lines)) if read == entry => writeln!(f, "{}", lines)?,
+                _ => write_entry(f, entry)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::currency::Currency;
+    use crate::units::moneybag::MoneyBag;
+
+    const JOURNAL: &str = "\
+; my books
+option \"title\" \"My books\"
+2024-01-01 open Assets:Bank
+2024-01-01 open Equity:Capital
+2024-01-01 commodity EUR
+
+2024/01/01 * Opening balance
+    Assets:Bank         1000.00 EUR
+    Equity:Capital
+
+2024-01-03 ! \"Landlord\" \"Rent\"  ; monthly
+    invoice: \"2024-001\"
+    Expenses:Rent        600.00 EUR  ; January
+    ; paid late
+    Assets:Bank         -600.00 EUR
+
+2024-01-31 balance Assets:Bank  400.00 EUR
+2024-12-31 close Expenses:Rent
+";
+
+    fn error(s: &str) -> String {
+        Journal::parse(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parse() {
+        let journal = Journal::parse(JOURNAL).unwrap();
+        assert_eq!(journal.entries.len(), 9);
+        assert_eq!(journal.entries[0], Entry::Comment("; my books".to_owned()));
+        assert_eq!(journal.entries[1], Entry::Directive("option \"title\" \"My books\"".to_owned()));
+        assert_eq!(journal.entries[4], Entry::Directive("2024-01-01 commodity EUR".to_owned()));
+        assert_eq!(journal.entries[8], Entry::Directive("2024-12-31 close Expenses:Rent".to_owned()));
+
+        let Entry::Transaction { flag, payee, transaction } = &journal.entries[5] else { panic!() };
+        assert_eq!(*flag, Some('*'));
+        assert_eq!(*payee, None);
+        assert_eq!(transaction.date, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(transaction.description, "Opening balance");
+        assert_eq!(transaction.postings[1].account, "Equity:Capital");
+        assert_eq!(transaction.postings[1].amount, Money::new(dec!(-1000), Currency::EUR));
+
+        let Entry::Transaction { flag, payee, transaction } = &journal.entries[6] else { panic!() };
+        assert_eq!(*flag, Some('!'));
+        assert_eq!(payee.as_deref(), Some("Landlord"));
+        assert_eq!(transaction.description, "Rent");
+        assert_eq!(transaction.postings.len(), 2);
+
+        let ledger = journal.to_ledger().unwrap();
+        assert_eq!(ledger.balance("Assets:Bank"), MoneyBag::from(Money::new(dec!(400), Currency::EUR)));
+        assert_eq!(ledger.account("Expenses:Rent").map(|a| a.kind), Some(AccountKind::Expense));
+    }
+
+    #[test]
+    fn headers() {
+        let narration = |s: &str| header(s).map_err(|(_, e)| e);
+
+        assert_eq!(narration("txn \"Rent\""), Ok((Some('*'), None, "Rent".to_owned())));
+        assert_eq!(narration("\"A \\\"quote\\\"\" \"; not a comment\" #tag ^link"), Ok((
+            None,
+            Some("A \"quote\"".to_owned()),
+            "; not a comment".to_owned()
+        )));
+        assert_eq!(narration("* Rent \"flat\""), Ok((Some('*'), None, "Rent \"flat\"".to_owned())));
+        assert_eq!(narration("* \"A\" \"B\" \"C\""), Err("expected a payee and a narration".to_owned()));
+        assert_eq!(error("2024-01-01 * \"Rent\n"), "line 1, column 14: unterminated string");
+        assert_eq!(error("2024-01-01 * \"Rent\" Jan\n"), "line 1, column 21: unexpected \"Jan\" after the narration");
+    }
+
+    #[test]
+    fn round_trip() {
+        let journal = Journal::parse(JOURNAL).unwrap();
+        assert_eq!(journal.to_string(), JOURNAL);
+
+        let salary = "2024-01-31 * Salary\n    Assets:Bank      $1,000.00\n    Income:Salary\n";
+        assert_eq!(Journal::parse(salary).unwrap().to_string(), salary);
+    }
+
+    #[test]
+    fn changed_entries() {
+        let mut journal = Journal::parse(JOURNAL).unwrap();
+        let Entry::Transaction { transaction, .. } = &mut journal.entries[6] else { panic!() };
+        transaction.description = "Rent and fees".to_owned();
+
+        let written = journal.to_string();
+        assert!(written.contains("2024-01-03 ! \"Landlord\" \"Rent and fees\"\n"));
+        assert!(written.contains("    Expenses:Rent    600.00 EUR\n"));
+        assert!(written.contains("    Assets:Bank     -600.00 EUR\n"));
+        assert!(written.contains("2024/01/01 * Opening balance\n"));
+        assert_eq!(Journal::parse(&written), Ok(journal));
+
+        // Nothing read, everything written from the entries
+        let journal = Journal { entries: Journal::parse(JOURNAL).unwrap().entries, ..Journal::default() };
+
+        let written = journal.to_string();
+        assert!(written.contains("2024-01-01 * Opening balance\n"));
+        assert!(written.contains("    Assets:Bank      1000.00 EUR\n"));
+        assert!(written.contains("    Equity:Capital  -1000.00 EUR\n"));
+        assert!(written.contains("2024-01-31 balance Assets:Bank  400.00 EUR\n"));
+        assert_eq!(Journal::parse(&written), Ok(journal));
+    }
+
+    #[test]
+    fn precision_is_kept() {
+        let fuel = Journal::parse("2024-01-01 Fuel\n  Expenses:Car  1.459 EUR\n  Assets:Bank  -1.459 EUR\n").unwrap();
+        let journal = Journal { entries: fuel.entries, ..Journal::default() };
+
+        assert!(journal.to_string().contains("1.459 EUR"));
+        assert!(journal.to_string().contains("-1.459 EUR"));
+    }
+
+    #[test]
+    fn directives() {
+        let journal = Journal::parse("account Assets:Bank\n    note Main account\n2024-01-01 price EUR 1.10 USD\n").unwrap();
+        assert_eq!(journal.entries, vec![
+            Entry::Directive("account Assets:Bank\n    note Main account".to_owned()),
+            Entry::Directive("2024-01-01 price EUR 1.10 USD".to_owned())
+        ]);
+
+        let journal = Journal::parse("2024-01-01 open Assets:Bank\n    name: \"Main\"\n").unwrap();
+        assert_eq!(journal.entries.len(), 1);
+        assert!(is_metadata("invoice-id: 42"));
+        assert!(!is_metadata("Assets:Bank  5 EUR"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("2024-13-01 Rent\n"), "line 1, column 1: invalid date \"2024-13-01\"");
+        assert_eq!(
+            error("2024-01-01 Rent\n    Expenses:Rent  600 EUR\n    Assets:Bank  -590 EUR\n"),
+            "line 1, column 1: transaction \"Rent\" doesn't balance, 10.00€ left over"
+        );
+        assert_eq!(
+            error("2024-01-01 Rent\n    Expenses:Rent  600 XYZ\n    Assets:Bank\n"),
+            "line 2, column 20: unknown currency \"XYZ\" in \"600 XYZ\""
+        );
+        assert_eq!(
+            error("2024-01-01 Rent\n    Stuff:Rent  600 EUR\n    Assets:Bank\n"),
+            "line 2, column 5: unknown account \"Stuff:Rent\""
+        );
+        assert_eq!(
+            error("2024-01-01 Rent\n    Expenses:Rent\n    Assets:Bank\n"),
+            "line 3, column 5: only one posting can leave its amount out"
+        );
+        assert_eq!(error("    Assets:Bank  5 EUR\n"), "line 1, column 5: posting outside of a transaction");
+        assert_eq!(
+            error("2024-01-01 open Assets:Bank\n    Assets:Bank  5 EUR\n"),
+            "line 2, column 5: posting outside of a transaction"
+        );
+    }
+
+    #[test]
+    fn balance_assertion() {
+        let journal = JOURNAL.replace("balance Assets:Bank  400.00 EUR", "balance Assets:Bank  500.00 EUR");
+        assert_eq!(
+            error(&journal),
+            "line 17, column 33: balance assertion failed: Assets:Bank is 400.00 EUR, expected 500.00 EUR"
+        );
+
+        // At the start of the day, the rent paid that day doesn't count yet
+        let journal = JOURNAL.replace("2024-01-31 balance Assets:Bank  400.00", "2024-01-03 balance Assets:Bank  1000.00");
+        assert!(Journal::parse(&journal).is_ok());
+        let journal = JOURNAL.replace("2024-01-31 balance Assets:Bank  400.00", "2024-01-04 balance Assets:Bank  400.00");
+        assert!(Journal::parse(&journal).is_ok());
+        let journal = JOURNAL.replace("2024-01-31 balance Assets:Bank  400.00", "2024-01-01 balance Assets:Bank  1000.00");
+        assert!(Journal::parse(&journal).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::units::money::Money;
+use crate::units::moneybag::MoneyBag;
+
+pub mod journal;
+
+/*
+    Double-entry ledger
+
+    A transaction moves money between accounts with postings:
+    a positive amount is a debit, a negative one a credit.
+    Every transaction must balance per currency, debits and credits
+    add up to zero for EUR, for USD... separately. Exchanging currencies
+    goes through an account in each currency (i.e Equity:Exchange).
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum AccountKind {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Account {
+    pub name: String,
+    pub kind: AccountKind
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Posting {
+    pub account: String,
+    pub amount: Money
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub description: String,
+    pub postings: Vec<Posting>
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum LedgerError {
+    // what is left over, per currency
+    Unbalanced { description: String, remainder: MoneyBag },
+    // a transaction needs at least two postings
+    TooFewPostings(String),
+    UnknownAccount(String),
+    DuplicateAccount(String)
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::Unbalanced { description, remainder } => {
+                write!(f, "transaction {:?} doesn't balance, {} left over", description, remainder)
+            },
+            LedgerError::TooFewPostings(description) => {
+                write!(f, "transaction {:?} needs at least two postings", description)
+            },
+            LedgerError::UnknownAccount(account) => write!(f, "unknown account {:?}", account),
+            LedgerError::DuplicateAccount(account) => write!(f, "account {:?} already exists", account),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+impl Transaction {
+    pub fn new(date: NaiveDate, description: &str) -> Transaction {
+        Transaction { date, description: description.to_owned(), postings: Vec::new() }
+    }
+
+    pub fn posting(mut self, account: &str, amount: Money) -> Transaction {
+        self.postings.push(Posting { account: account.to_owned(), amount });
+        self
+    }
+
+    // Sum of the postings, empty when the transaction balances
+    pub fn remainder(&self) -> MoneyBag {
+        self.postings.iter().map(|posting| posting.amount).collect()
+    }
+
+    pub fn check(&self) -> Result<(), LedgerError> {
+        if self.postings.len() < 2 {
+            return Err(LedgerError::TooFewPostings(self.description.clone()));
+        }
+
+        let remainder = self.remainder();
+        if !remainder.is_empty() {
+            return Err(LedgerError::Unbalanced { description: self.description.clone(), remainder });
+        }
+
+        Ok(())
+    }
+}
+
+// One line of an account statement
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BalanceEntry {
+    pub date: NaiveDate,
+    pub description: String,
+    pub amount: Money,
+    // balance of the account after this posting
+    pub balance: MoneyBag
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    accounts: BTreeMap<String, Account>,
+    // sorted by date, in insertion order on the same day
+    transactions: Vec<Transaction>
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    pub fn open(&mut self, name: &str, kind: AccountKind) -> Result<(), LedgerError> {
+        if self.accounts.contains_key(name) {
+            return Err(LedgerError::DuplicateAccount(name.to_owned()));
+        }
+
+        self.accounts.insert(name.to_owned(), Account { name: name.to_owned(), kind });
+        Ok(())
+    }
+
+    pub fn account(&self, name: &str) -> Option<&Account> {
+        self.accounts.get(name)
+    }
+
+    // Sorted by name
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    // Rejects unbalanced transactions and postings to accounts that weren't opened
+    pub fn add(&mut self, transaction: Transaction) -> Result<(), LedgerError> {
+        transaction.check()?;
+
+        if let Some(posting) = transaction.postings.iter().find(|p| !self.accounts.contains_key(&p.account)) {
+            return Err(LedgerError::UnknownAccount(posting.account.clone()));
+        }
+
+        let index = self.transactions.partition_point(|t| t.date <= transaction.date);
+        self.transactions.insert(index, transaction);
+        Ok(())
+    }
+
+    pub fn balance(&self, account: &str) -> MoneyBag {
+        self.postings(account).map(|(_, posting)| posting.amount).collect()
+    }
+
+    // Balance at the end of `date`
+    pub fn balance_at(&self, account: &str, date: NaiveDate) -> MoneyBag {
+        self.postings(account)
+            .filter(|(transaction, _)| transaction.date <= date)
+            .map(|(_, posting)| posting.amount)
+            .collect()
+    }
+
+    // Every posting of `account` with the balance after it
+    pub fn running_balance(&self, account: &str) -> Vec<BalanceEntry> {
+        let mut balance = MoneyBag::new();
+
+        self.postings(account).map(|(transaction, posting)| {
+            balance = balance.clone() + posting.amount;
+            BalanceEntry {
+                date: transaction.date,
+                description: transaction.description.clone(),
+                amount: posting.amount,
+                balance: balance.clone()
+            }
+        }).collect()
+    }
+
+    pub fn trial_balance(&self) -> TrialBalance {
+        let rows = self.accounts.keys().map(|account| {
+            let balance = self.balance(account);
+            TrialBalanceRow {
+                account: account.clone(),
+                debit: balance.iter().filter(|money| money.amount > Decimal::ZERO).collect(),
+                credit: balance.iter()
+                    .filter(|money| money.amount < Decimal::ZERO)
+                    .map(|money| Money::new(-money.amount, money.currency))
+                    .collect()
+            }
+        }).collect();
+
+        TrialBalance { rows }
+    }
+
+    fn postings<'a>(&'a self, account: &'a str) -> impl Iterator<Item = (&'a Transaction, &'a Posting)> + 'a {
+        self.transactions.iter().flat_map(move |transaction| {
+            transaction.postings.iter()
+                .filter(move |posting| posting.account == account)
+                .map(move |posting| (transaction, posting))
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TrialBalanceRow {
+    pub account: String,
+    pub debit: MoneyBag,
+    pub credit: MoneyBag
+}
+
+/*
+    Trial balance
+
+    The balance of every account, debit balances on the left and
+    credit balances on the right. Both columns always add up to the
+    same totals since every transaction balances.
+*/
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TrialBalance {
+    pub rows: Vec<TrialBalanceRow>
+}
+
+impl TrialBalance {
+    pub fn total_debit(&self) -> MoneyBag {
+        self.rows.iter().fold(MoneyBag::new(), |total, row| total + row.debit.clone())
+    }
+
+    pub fn total_credit(&self) -> MoneyBag {
+        self.rows.iter().fold(MoneyBag::new(), |total, row| total + row.credit.clone())
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.total_debit() == self.total_credit()
+    }
+}
+
+impl fmt::Display for TrialBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rows.iter().map(|row| row.account.chars().count()).max().unwrap_or(0).max(5);
+        let column = |bag: &MoneyBag| if bag.is_empty() { String::new() } else { bag.to_string() };
+
+        for row in &self.rows {
+            writeln!(f, "{:<width$}  {:>16}  {:>16}", row.account, column(&row.debit), column(&row.credit))?;
+        }
+        write!(f, "{:<width$}  {:>16}  {:>16}", "Total", column(&self.total_debit()), column(&self.total_credit()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::{eur, usd};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn ledger() -> Ledger {
+        let mut ledger = Ledger::new();
+        ledger.open("Assets:Bank", AccountKind::Asset).unwrap();
+        ledger.open("Assets:Bank:USD", AccountKind::Asset).unwrap();
+        ledger.open("Equity:Capital", AccountKind::Equity).unwrap();
+        ledger.open("Equity:Exchange", AccountKind::Equity).unwrap();
+        ledger.open("Expenses:Rent", AccountKind::Expense).unwrap();
+        ledger
+    }
+
+    #[test]
+    fn balanced() {
+        let mut ledger = ledger();
+        ledger.add(Transaction::new(date(1), "Capital")
+            .posting("Assets:Bank", eur(dec!(1000)))
+            .posting("Equity:Capital", eur(dec!(-1000)))).unwrap();
+        ledger.add(Transaction::new(date(3), "Rent")
+            .posting("Expenses:Rent", eur(dec!(600)))
+            .posting("Assets:Bank", eur(dec!(-600)))).unwrap();
+
+        assert_eq!(ledger.balance("Assets:Bank"), MoneyBag::from(eur(dec!(400))));
+        assert_eq!(ledger.balance("Expenses:Rent"), MoneyBag::from(eur(dec!(600))));
+    }
+
+    #[test]
+    fn unbalanced() {
+        let mut ledger = ledger();
+        let error = ledger.add(Transaction::new(date(1), "Rent")
+            .posting("Expenses:Rent", eur(dec!(600)))
+            .posting("Assets:Bank", eur(dec!(-590)))).unwrap_err();
+
+        assert_eq!(error, LedgerError::Unbalanced { description: "Rent".to_owned(), remainder: MoneyBag::from(eur(dec!(10))) });
+        assert_eq!(error.to_string(), "transaction \"Rent\" doesn't balance, 10.00€ left over");
+        assert!(ledger.transactions().is_empty());
+
+        // balancing in total is not enough, every currency must balance
+        let error = ledger.add(Transaction::new(date(1), "Exchange")
+            .posting("Assets:Bank:USD", usd(dec!(110)))
+            .posting("Assets:Bank", eur(dec!(-100)))).unwrap_err();
+        assert!(matches!(error, LedgerError::Unbalanced { .. }));
+
+        let error = ledger.add(Transaction::new(date(1), "Nothing").posting("Assets:Bank", eur(dec!(0)))).unwrap_err();
+        assert_eq!(error, LedgerError::TooFewPostings("Nothing".to_owned()));
+    }
+
+    #[test]
+    fn accounts() {
+        let mut ledger = ledger();
+        assert_eq!(ledger.open("Assets:Bank", AccountKind::Asset), Err(LedgerError::DuplicateAccount("Assets:Bank".to_owned())));
+        assert_eq!(ledger.account("Expenses:Rent").map(|a| a.kind), Some(AccountKind::Expense));
+
+        let error = ledger.add(Transaction::new(date(1), "Coffee")
+            .posting("Expenses:Coffee", eur(dec!(3)))
+            .posting("Assets:Bank", eur(dec!(-3)))).unwrap_err();
+        assert_eq!(error, LedgerError::UnknownAccount("Expenses:Coffee".to_owned()));
+    }
+
+    #[test]
+    fn running_balance() {
+        let mut ledger = ledger();
+        ledger.add(Transaction::new(date(5), "Rent")
+            .posting("Expenses:Rent", eur(dec!(600)))
+            .posting("Assets:Bank", eur(dec!(-600)))).unwrap();
+        // added later but happened before
+        ledger.add(Transaction::new(date(1), "Capital")
+            .posting("Assets:Bank", eur(dec!(1000)))
+            .posting("Equity:Capital", eur(dec!(-1000)))).unwrap();
+
+        let entries = ledger.running_balance("Assets:Bank");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].description, "Capital");
+        assert_eq!(entries[0].balance, MoneyBag::from(eur(dec!(1000))));
+        assert_eq!(entries[1].amount, eur(dec!(-600)));
+        assert_eq!(entries[1].balance, MoneyBag::from(eur(dec!(400))));
+    }
+
+    #[test]
+    fn trial_balance() {
+        let mut ledger = ledger();
+        ledger.add(Transaction::new(date(1), "Capital")
+            .posting("Assets:Bank", eur(dec!(1000)))
+            .posting("Equity:Capital", eur(dec!(-1000)))).unwrap();
+        ledger.add(Transaction::new(date(2), "Exchange")
+            .posting("Assets:Bank:USD", usd(dec!(110)))
+            .posting("Equity:Exchange", usd(dec!(-110)))
+            .posting("Equity:Exchange", eur(dec!(100)))
+            .posting("Assets:Bank", eur(dec!(-100)))).unwrap();
+
+        let trial = ledger.trial_balance();
+        assert!(trial.is_balanced());
+        assert_eq!(trial.total_debit(), MoneyBag::from(eur(dec!(1000))) + usd(dec!(110)));
+
+        let exchange = trial.rows.iter().find(|row| row.account == "Equity:Exchange").unwrap();
+        assert_eq!(exchange.debit, MoneyBag::from(eur(dec!(100))));
+        assert_eq!(exchange.credit, MoneyBag::from(usd(dec!(110))));
+
+        assert_eq!(trial.to_string().lines().last(), Some("Total            1000.00€ + $110.00  1000.00€ + $110.00"));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod units;
+pub mod tax;
+pub mod pricing;
+pub mod finance;
+pub mod ledger;
+pub mod statement;
+
+use units::percentage::Percentage;
+use units::money::Money;
@@ -1,21 +1,24 @@
-mod units;
-use units::percentage::Percentage;
-use units::restype::ResType;
-use units::money::{Money, Currency};
+use rust_decimal_macros::dec;
+use type_system::units::percentage::Percentage;
+use type_system::units::restype::ResType;
+use type_system::units::money::Money;
+use type_system::units::currency::Currency;
 
 
 fn main() {
-    let a = ResType::Money(Money::new(42.0, Currency::Euros));
+    let a = ResType::Money(Money::new(dec!(42), Currency::EUR));
     let b = ResType::Percent(Percentage::new(20.0));
 
     let c = a + b;
     println!("a + b: {}", c);
 
-    println!("12% of 42€: {}", Money::new(42.0, Currency::Euros) * Percentage::new(12.0));
+    println!("12% of 42€: {}", Money::new(dec!(42), Currency::EUR) * Percentage::new(12.0));
 
-    println!("42€ + 12: {}", Money::new(42.0, Currency::Euros) + 12);
+    println!("42€ + 12: {}", Money::new(dec!(42), Currency::EUR) + 12);
     println!("12 + 11%: {}", ResType::Int(12) + ResType::Percent(Percentage::new(11.0)));
 
-    let x = Percentage::new(13.0) / Percentage::new(0.0);
-    println!("{}", x);
+    match Percentage::new(13.0).checked_div(Percentage::new(0.0)) {
+        Ok(x) => println!("13% / 0%: {}", x),
+        Err(e) => println!("13% / 0%: {}", e),
+    }
 }
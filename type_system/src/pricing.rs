@@ -0,0 +1,196 @@
+use crate::units::error::UnitError;
+use crate::units::money::Money;
+use crate::units::percentage::Percentage;
+use crate::units::rounding::RoundingPolicy;
+
+/*
+    Successive price adjustments
+
+    list price                  100.00€
+    trade discount      -15%     85.00€
+    early payment        -2%     83.30€
+    VAT                 +19%     99.13€
+
+    Adjustments apply in order, each one to the amount before it,
+    and every intermediate amount is rounded. The whole chain above
+    is a single change of -0.87%, not the -15 - 2 + 19 = +2 points
+    a naive sum would give.
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Adjustment {
+    // -15% is a discount, +19% a markup
+    Percent(Percentage),
+    // -5€ off, +4.90€ shipping
+    Fixed(Money)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AdjustmentStep {
+    pub description: String,
+    pub adjustment: Adjustment
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PriceAdjustment {
+    pub rounding: RoundingPolicy,
+    steps: Vec<AdjustmentStep>
+}
+
+// An applied step: how much it changed and where it left the price
+#[derive(Debug, PartialEq, Clone)]
+pub struct AdjustedStep {
+    pub description: String,
+    pub adjustment: Adjustment,
+    pub change: Money,
+    pub amount: Money
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AdjustedPrice {
+    pub list: Money,
+    // one per adjustment, in order
+    pub steps: Vec<AdjustedStep>,
+    pub total: Money
+}
+
+impl PriceAdjustment {
+    pub fn new() -> PriceAdjustment {
+        PriceAdjustment { rounding: RoundingPolicy::default(), steps: Vec::new() }
+    }
+
+    pub fn with_rounding(self, rounding: RoundingPolicy) -> PriceAdjustment {
+        PriceAdjustment { rounding, ..self }
+    }
+
+    // A discount of 15% is `percent("trade discount", Percentage::new(-15.0))`
+    pub fn percent(mut self, description: &str, percentage: Percentage) -> PriceAdjustment {
+        self.add(description, Adjustment::Percent(percentage));
+        self
+    }
+
+    pub fn fixed(mut self, description: &str, amount: Money) -> PriceAdjustment {
+        self.add(description, Adjustment::Fixed(amount));
+        self
+    }
+
+    pub fn add(&mut self, description: &str, adjustment: Adjustment) {
+        self.steps.push(AdjustmentStep { description: description.to_owned(), adjustment });
+    }
+
+    pub fn steps(&self) -> &[AdjustmentStep] {
+        &self.steps
+    }
+
+    // Fails if a fixed adjustment is in another currency than `list`
+    pub fn apply(&self, list: Money) -> Result<AdjustedPrice, UnitError> {
+        let mut amount = list;
+        let mut steps = Vec::with_capacity(self.steps.len());
+
+        for step in &self.steps {
+            let next = match step.adjustment {
+                Adjustment::Percent(percentage) => {
+                    let part = Money::new(amount.checked_percentage_part(percentage)?, amount.currency);
+                    amount.checked_add(part)?.round(self.rounding)
+                },
+                Adjustment::Fixed(fixed) => amount.checked_add(fixed)?.round(self.rounding),
+            };
+
+            steps.push(AdjustedStep {
+                description: step.description.clone(),
+                adjustment: step.adjustment,
+                change: next.checked_sub(amount)?,
+                amount: next
+            });
+            amount = next;
+        }
+
+        Ok(AdjustedPrice { list, steps, total: amount })
+    }
+
+    /*
+        The single percentage the chain amounts to, before rounding.
+        A fixed adjustment has no percentage, its effect depends on the
+        price: use AdjustedPrice::effective_percentage for a given one.
+    */
+    pub fn effective_percentage(&self) -> Result<Percentage, UnitError> {
+        let percentages = self.steps.iter().map(|step| match step.adjustment {
+            Adjustment::Percent(percentage) => Ok(percentage),
+            Adjustment::Fixed(amount) => Err(UnitError::IncompatibleUnits(format!("{} {} as a percentage", step.description, amount))),
+        });
+        Percentage::compound_all(percentages.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+impl Default for PriceAdjustment {
+    fn default() -> Self {
+        PriceAdjustment::new()
+    }
+}
+
+impl AdjustedPrice {
+    // From the list price to the total, rounding included
+    pub fn effective_percentage(&self) -> Result<Percentage, UnitError> {
+        self.list.change_to(self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::eur;
+    use crate::units::currency::Currency;
+    use crate::units::rounding::RoundingMode;
+
+    fn retail() -> PriceAdjustment {
+        PriceAdjustment::new()
+            .percent("trade discount", Percentage::new(-15.0))
+            .percent("early payment", Percentage::new(-2.0))
+            .percent("VAT", Percentage::new(19.0))
+    }
+
+    #[test]
+    fn chain() {
+        let price = retail().apply(eur(dec!(100))).unwrap();
+
+        let amounts: Vec<Money> = price.steps.iter().map(|step| step.amount).collect();
+        assert_eq!(amounts, vec![eur(dec!(85)), eur(dec!(83.30)), eur(dec!(99.13))]);
+        assert_eq!(price.steps[1].change, eur(dec!(-1.70)));
+        assert_eq!(price.steps[2].description, "VAT");
+        assert_eq!(price.total, eur(dec!(99.13)));
+
+        assert_eq!(price.effective_percentage(), Ok(Percentage::new(-0.87)));
+        let effective = retail().effective_percentage().unwrap();
+        assert!((effective.value - -0.873).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_amounts() {
+        let adjustment = PriceAdjustment::new()
+            .percent("discount", Percentage::new(-10.0))
+            .fixed("voucher", eur(dec!(-5)))
+            .fixed("shipping", eur(dec!(4.90)));
+
+        let price = adjustment.apply(eur(dec!(50))).unwrap();
+        assert_eq!(price.total, eur(dec!(44.90)));
+        assert_eq!(price.steps[1].change, eur(dec!(-5)));
+        assert!(matches!(adjustment.effective_percentage(), Err(UnitError::IncompatibleUnits(_))));
+        assert_eq!(price.effective_percentage(), Ok(Percentage::new(-10.2)));
+
+        let usd = Money::new(dec!(1), Currency::USD);
+        assert!(matches!(PriceAdjustment::new().fixed("fee", usd).apply(eur(dec!(1))), Err(UnitError::IncompatibleUnits(_))));
+    }
+
+    #[test]
+    fn rounding_and_empty() {
+        let floor = RoundingPolicy::new(RoundingMode::Floor);
+        let price = retail().with_rounding(floor).apply(eur(dec!(9.99))).unwrap();
+        assert_eq!(price.steps[0].amount, eur(dec!(8.49)));
+        assert_eq!(price.total, eur(dec!(9.90)));
+
+        let nothing = PriceAdjustment::new().apply(eur(dec!(10))).unwrap();
+        assert_eq!(nothing.total, eur(dec!(10)));
+        assert!(nothing.steps.is_empty());
+        assert_eq!(PriceAdjustment::new().effective_percentage(), Ok(Percentage::new(0.0)));
+    }
+}
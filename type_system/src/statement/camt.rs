@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use chrono::NaiveDate;
+use roxmltree::Node;
+use rust_decimal::Decimal;
+
+use super::{Statement, StatementError, StatementLine};
+use crate::units::currency::Currency;
+use crate::units::money::Money;
+
+/*
+    ISO 20022 CAMT.053 (bank to customer statement)
+
+    <Stmt>
+        <Acct><Id><IBAN>DE89...</IBAN></Id><Ccy>EUR</Ccy></Acct>
+        <Bal><Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp><Amt Ccy="EUR">1000.00</Amt>...</Bal>
+        <Ntry><Amt Ccy="EUR">600.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts>BOOK</Sts>...</Ntry>
+    </Stmt>
+
+    Any version of the schema, only the booked entries are imported.
+    OPBD/PRCD is the opening balance, CLBD the closing one.
+*/
+pub fn parse(s: &str) -> Result<Vec<Statement>, StatementError> {
+    let document = roxmltree::Document::parse(s).map_err(|e| invalid(e.to_string()))?;
+
+    let statements = document.descendants()
+        .filter(|node| node.has_tag_name("Stmt"))
+        .map(statement)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for statement in &statements {
+        statement.validate()?;
+    }
+
+    Ok(statements)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Statement>, StatementError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+fn statement(node: Node) -> Result<Statement, StatementError> {
+    let account = child(node, "Acct").ok_or_else(|| invalid("statement without account".to_owned()))?;
+    let id = child(account, "Id")
+        .and_then(|id| id.children().find(|n| n.is_element()))
+        .and_then(|id| if id.has_tag_name("Othr") { child(id, "Id") } else { Some(id) })
+        .and_then(|id| id.text())
+        .ok_or_else(|| invalid("account without id".to_owned()))?;
+
+    let first_amount = node.descendants().find(|n| n.has_tag_name("Amt"));
+    let code = child(account, "Ccy").and_then(|ccy| ccy.text())
+        .or_else(|| first_amount.and_then(|amt| amt.attribute("Ccy")))
+        .ok_or_else(|| invalid(format!("no currency for account {}", id)))?;
+
+    let mut statement = Statement::new(id.trim(), currency(code)?);
+
+    for balance in node.children().filter(|n| n.has_tag_name("Bal")) {
+        let kind = child(balance, "Tp").and_then(|tp| descendant_text(tp, "Cd")).unwrap_or_default();
+        match kind {
+            "OPBD" | "PRCD" => statement.opening = Some(amount(balance)?),
+            "CLBD" => statement.closing = Some(amount(balance)?),
+            _ => {},
+        }
+    }
+
+    for entry in node.children().filter(|n| n.has_tag_name("Ntry")) {
+        let status = child(entry, "Sts").map(|sts| descendant_text(sts, "Cd").or(sts.text()).unwrap_or_default().trim());
+        if status.is_some_and(|status| status != "BOOK") {
+            continue;
+        }
+
+        let date = child(entry, "BookgDt").map(node_date).transpose()?
+            .ok_or_else(|| invalid("entry without booking date".to_owned()))?;
+        let value_date = child(entry, "ValDt").map(node_date).transpose()?;
+
+        let description = descendant_text(entry, "Ustrd")
+            .or_else(|| descendant_text(entry, "AddtlNtryInf"))
+            .unwrap_or_default();
+
+        statement.lines.push(StatementLine {
+            date,
+            value_date,
+            amount: amount(entry)?,
+            description: description.trim().to_owned(),
+            reference: descendant_text(entry, "AcctSvcrRef").map(|r| r.trim().to_owned())
+        });
+    }
+
+    Ok(statement)
+}
+
+// <Amt Ccy="EUR">600.00</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+fn amount(node: Node) -> Result<Money, StatementError> {
+    let amt = child(node, "Amt").ok_or_else(|| invalid("missing amount".to_owned()))?;
+    let text = amt.text().unwrap_or_default().trim();
+    let value = Decimal::from_str(text).map_err(|_| invalid(format!("invalid amount {:?}", text)))?;
+    let code = amt.attribute("Ccy").ok_or_else(|| invalid(format!("amount {} without currency", text)))?;
+
+    let value = match child(node, "CdtDbtInd").and_then(|ind| ind.text()).map(str::trim) {
+        Some("CRDT") => value,
+        Some("DBIT") => -value,
+        other => return Err(invalid(format!("invalid credit/debit indicator {:?}", other.unwrap_or_default()))),
+    };
+
+    Ok(Money::new(value, currency(code)?))
+}
+
+// <Dt>2024-01-03</Dt> or <DtTm>2024-01-20T10:32:00</DtTm>
+fn node_date(node: Node) -> Result<NaiveDate, StatementError> {
+    let text = descendant_text(node, "Dt").or_else(|| descendant_text(node, "DtTm")).unwrap_or_default().trim();
+    text.get(..10)
+        .and_then(|day| NaiveDate::from_str(day).ok())
+        .ok_or_else(|| invalid(format!("invalid date {:?}", text)))
+}
+
+fn currency(code: &str) -> Result<Currency, StatementError> {
+    Currency::from_code(code.trim()).ok_or_else(|| invalid(format!("unknown currency {:?}", code)))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn descendant_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.descendants().find(|n| n.has_tag_name(name) && n.text().is_some_and(|t| !t.trim().is_empty()))?.text()
+}
+
+fn invalid(message: String) -> StatementError {
+    StatementError::Invalid { format: "CAMT.053", message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn fixture() -> String {
+        fs::read_to_string(format!("{}/fixtures/camt053.xml", env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn import() {
+        let statements = load(format!("{}/fixtures/camt053.xml", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(statements.len(), 1);
+
+        let statement = &statements[0];
+        assert_eq!(statement.account, "DE89370400440532013000");
+        assert_eq!(statement.currency, Currency::EUR);
+        assert_eq!(statement.opening, Some(Money::new(dec!(1000), Currency::EUR)));
+        assert_eq!(statement.closing, Some(Money::new(dec!(2854.10), Currency::EUR)));
+
+        // the pending entry is left out
+        assert_eq!(statement.lines.len(), 3);
+        assert_eq!(statement.lines[0], StatementLine {
+            date: date("2024-01-03"),
+            value_date: Some(date("2024-01-03")),
+            amount: Money::new(dec!(-600), Currency::EUR),
+            description: "Rent January".to_owned(),
+            reference: Some("REF-0001".to_owned())
+        });
+        assert_eq!(statement.lines[2].date, date("2024-01-20"));
+        assert_eq!(statement.lines[2].value_date, Some(date("2024-01-22")));
+        assert_eq!(statement.lines[2].description, "Groceries");
+    }
+
+    #[test]
+    fn unbalanced() {
+        let tampered = fixture().replace("2854.10", "2864.10");
+        assert!(matches!(parse(&tampered), Err(StatementError::Unbalanced { .. })));
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert!(matches!(parse("<Document>"), Err(StatementError::Invalid { format: "CAMT.053", .. })));
+
+        let error = parse(&fixture().replace("<CdtDbtInd>DBIT</CdtDbtInd>\n        <Sts>BOOK", "<CdtDbtInd>X</CdtDbtInd>\n        <Sts>BOOK"));
+        assert_eq!(error.unwrap_err().to_string(), "invalid CAMT.053 statement: invalid credit/debit indicator \"X\"");
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::units::currency::Currency;
+use crate::units::error::UnitError;
+use crate::units::money::Money;
+
+pub mod camt;
+pub mod mt940;
+pub mod ofx;
+
+/*
+    Bank statements
+
+    Every importer turns a file into statements: the account, its
+    currency, the booked transactions and the opening and closing
+    balances when the format has them. A statement with both balances
+    is only returned if opening + transactions = closing.
+*/
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct StatementLine {
+    // booking date
+    pub date: NaiveDate,
+    pub value_date: Option<NaiveDate>,
+    // negative for debits
+    pub amount: Money,
+    pub description: String,
+    pub reference: Option<String>
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Statement {
+    // IBAN or account number
+    pub account: String,
+    pub currency: Currency,
+    pub opening: Option<Money>,
+    pub closing: Option<Money>,
+    pub lines: Vec<StatementLine>
+}
+
+#[derive(Debug)]
+pub enum StatementError {
+    Io(std::io::Error),
+    // format is "CAMT.053", "MT940" or "OFX"
+    Invalid { format: &'static str, message: String },
+    Unit(UnitError),
+    // closing balance given by the bank vs opening + transactions
+    Unbalanced { account: String, currency: Currency, closing: Decimal, computed: Decimal }
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementError::Io(e) => write!(f, "can't read statement: {}", e),
+            StatementError::Invalid { format, message } => write!(f, "invalid {} statement: {}", format, message),
+            StatementError::Unit(e) => write!(f, "{}", e),
+            StatementError::Unbalanced { account, currency, closing, computed } => write!(
+                f, "statement of {} doesn't balance: closing balance is {} but the transactions add up to {}",
+                account, Money::new(*closing, *currency), Money::new(*computed, *currency)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StatementError {}
+
+impl From<std::io::Error> for StatementError {
+    fn from(e: std::io::Error) -> Self {
+        StatementError::Io(e)
+    }
+}
+
+impl From<UnitError> for StatementError {
+    fn from(e: UnitError) -> Self {
+        StatementError::Unit(e)
+    }
+}
+
+impl Statement {
+    pub fn new(account: &str, currency: Currency) -> Statement {
+        Statement { account: account.to_owned(), currency, opening: None, closing: None, lines: Vec::new() }
+    }
+
+    // Sum of the transactions
+    pub fn total(&self) -> Result<Money, UnitError> {
+        self.lines.iter().try_fold(Money::new(Decimal::ZERO, self.currency), |total, line| total.checked_add(line.amount))
+    }
+
+    // opening + transactions = closing, when both balances are known
+    pub fn validate(&self) -> Result<(), StatementError> {
+        let total = self.total()?;
+
+        if let (Some(opening), Some(closing)) = (self.opening, self.closing) {
+            let computed = opening.checked_add(total)?;
+            // fails too when the closing balance is in another currency
+            let difference = closing.checked_sub(computed)?;
+            if !difference.amount.is_zero() {
+                return Err(StatementError::Unbalanced {
+                    account: self.account.clone(),
+                    currency: closing.currency,
+                    closing: closing.amount,
+                    computed: computed.amount
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::eur;
+
+    fn line(amount: Decimal) -> StatementLine {
+        StatementLine {
+            date: NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+            value_date: None,
+            amount: eur(amount),
+            description: String::new(),
+            reference: None
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut statement = Statement::new("DE89370400440532013000", Currency::EUR);
+        statement.lines = vec![line(dec!(-600)), line(dec!(2500))];
+        assert_eq!(statement.total(), Ok(eur(dec!(1900))));
+        assert!(statement.validate().is_ok());
+
+        statement.opening = Some(eur(dec!(1000)));
+        statement.closing = Some(eur(dec!(2900)));
+        assert!(statement.validate().is_ok());
+
+        statement.closing = Some(eur(dec!(2800)));
+        assert_eq!(
+            statement.validate().unwrap_err().to_string(),
+            "statement of DE89370400440532013000 doesn't balance: closing balance is 2800.00€ but the transactions add up to 2900.00€"
+        );
+
+        statement.lines.push(StatementLine { amount: Money::new(dec!(1), Currency::USD), ..line(dec!(0)) });
+        assert!(matches!(statement.validate(), Err(StatementError::Unit(UnitError::IncompatibleUnits(_)))));
+    }
+}
@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+
+use super::{Statement, StatementError, StatementLine};
+use crate::units::currency::Currency;
+use crate::units::money::Money;
+
+/*
+    SWIFT MT940
+
+    :20:STMT-2024-01
+    :25:DE89370400440532013000
+    :60F:C240101EUR1000,00
+    :61:2401030103D600,00NTRFNONREF//REF-0001
+    :86:Rent January
+    :62F:C240131EUR2854,10
+
+    :61: is value date (YYMMDD), optional booking date (MMDD), C/D/RC/RD,
+    optional funds code, amount with a decimal comma, then the transaction
+    type and references. :86: is the description of the :61: before it,
+    and like every field it may go on over several lines.
+    Each :20: starts a new statement.
+*/
+pub fn parse(s: &str) -> Result<Vec<Statement>, StatementError> {
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut account = String::new();
+
+    for (tag, value) in fields(s) {
+        match tag {
+            "20" => account.clear(),
+            "25" => account = value.trim().to_owned(),
+            "60F" | "60M" => {
+                let opening = balance(&value)?;
+                let mut statement = Statement::new(&account, opening.currency);
+                // an intermediate (M) balance starts a new page, checked on its own
+                statement.opening = Some(opening);
+                statements.push(statement);
+            },
+            "61" => {
+                let statement = statements.last_mut().ok_or_else(|| invalid(":61: before the opening balance".to_owned()))?;
+                let line = statement_line(&value, statement.currency)?;
+                statement.lines.push(line);
+            },
+            "86" => {
+                let line = statements.last_mut().and_then(|statement| statement.lines.last_mut())
+                    .ok_or_else(|| invalid(":86: without a :61: before it".to_owned()))?;
+                line.description = value.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+            },
+            "62F" | "62M" => {
+                let statement = statements.last_mut().ok_or_else(|| invalid(":62: before the opening balance".to_owned()))?;
+                statement.closing = Some(balance(&value)?);
+            },
+            _ => {},
+        }
+    }
+
+    for statement in &statements {
+        statement.validate()?;
+    }
+
+    Ok(statements)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Statement>, StatementError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+// (tag, value), the value includes its continuation lines
+fn fields(s: &str) -> Vec<(&str, String)> {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+
+    for line in s.lines() {
+        let line = line.trim_end();
+        let tagged = line.strip_prefix(':').and_then(|rest| rest.split_once(':'));
+
+        match (tagged, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag, value.to_owned())),
+            // end of message
+            (None, _) if line == "-" || line.starts_with("-}") => {},
+            (None, Some((_, value))) => {
+                value.push('\n');
+                value.push_str(line);
+            },
+            (None, None) => {},
+        }
+    }
+
+    fields
+}
+
+// C240101EUR1000,00
+fn balance(s: &str) -> Result<Money, StatementError> {
+    let s = s.trim();
+    let (mark, rest) = s.split_at_checked(1).ok_or_else(|| invalid(format!("invalid balance {:?}", s)))?;
+    let (date, rest) = rest.split_at_checked(6).ok_or_else(|| invalid(format!("invalid balance {:?}", s)))?;
+    let (code, amount) = rest.split_at_checked(3).ok_or_else(|| invalid(format!("invalid balance {:?}", s)))?;
+    short_date(date)?;
+
+    let amount = decimal(amount)?;
+    let currency = Currency::from_code(code).ok_or_else(|| invalid(format!("unknown currency {:?}", code)))?;
+
+    match mark {
+        "C" => Ok(Money::new(amount, currency)),
+        "D" => Ok(Money::new(-amount, currency)),
+        _ => Err(invalid(format!("invalid debit/credit mark {:?}", mark))),
+    }
+}
+
+// 2401030103D600,00NTRFNONREF//REF-0001
+fn statement_line(s: &str, currency: Currency) -> Result<StatementLine, StatementError> {
+    let s = s.lines().next().unwrap_or_default().trim();
+    let error = || invalid(format!("invalid :61: line {:?}", s));
+
+    let value_date = short_date(s.get(..6).ok_or_else(error)?)?;
+    let mut rest = &s[6..];
+
+    // booking date, same year as the value date unless it crosses new year
+    let mut date = value_date;
+    if rest.get(..4).is_some_and(|digits| digits.bytes().all(|b| b.is_ascii_digit())) {
+        let month: u32 = rest[..2].parse().map_err(|_| error())?;
+        let day: u32 = rest[2..4].parse().map_err(|_| error())?;
+        let year = match i64::from(month) - i64::from(value_date.month()) {
+            diff if diff > 6 => value_date.year() - 1,
+            diff if diff < -6 => value_date.year() + 1,
+            _ => value_date.year(),
+        };
+        date = NaiveDate::from_ymd_opt(year, month, day).ok_or_else(error)?;
+        rest = &rest[4..];
+    }
+
+    let (sign, rest) = if let Some(rest) = rest.strip_prefix("RC") {
+        (Decimal::NEGATIVE_ONE, rest)
+    } else if let Some(rest) = rest.strip_prefix("RD") {
+        (Decimal::ONE, rest)
+    } else if let Some(rest) = rest.strip_prefix('C') {
+        (Decimal::ONE, rest)
+    } else if let Some(rest) = rest.strip_prefix('D') {
+        (Decimal::NEGATIVE_ONE, rest)
+    } else {
+        return Err(error());
+    };
+
+    // funds code, the third letter of the currency code
+    let rest = rest.strip_prefix(|c: char| c.is_ascii_alphabetic()).unwrap_or(rest);
+
+    let end = rest.find(|c: char| !(c.is_ascii_digit() || c == ',')).unwrap_or(rest.len());
+    let amount = decimal(&rest[..end])?;
+
+    let reference = rest[end..].split_once("//").map(|(_, r)| r.trim().to_owned()).filter(|r| !r.is_empty());
+
+    Ok(StatementLine {
+        date,
+        value_date: Some(value_date),
+        amount: Money::new(sign * amount, currency),
+        description: String::new(),
+        reference
+    })
+}
+
+// 240103
+fn short_date(s: &str) -> Result<NaiveDate, StatementError> {
+    NaiveDate::parse_from_str(s, "%y%m%d").map_err(|_| invalid(format!("invalid date {:?}", s)))
+}
+
+// 1000,00
+fn decimal(s: &str) -> Result<Decimal, StatementError> {
+    Decimal::from_str(&s.trim().replace(',', ".")).map_err(|_| invalid(format!("invalid amount {:?}", s)))
+}
+
+fn invalid(message: String) -> StatementError {
+    StatementError::Invalid { format: "MT940", message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn fixture() -> String {
+        fs::read_to_string(format!("{}/fixtures/mt940.sta", env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn import() {
+        let statements = load(format!("{}/fixtures/mt940.sta", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(statements.len(), 1);
+
+        let statement = &statements[0];
+        assert_eq!(statement.account, "DE89370400440532013000");
+        assert_eq!(statement.opening, Some(Money::new(dec!(1000), Currency::EUR)));
+        assert_eq!(statement.closing, Some(Money::new(dec!(2854.10), Currency::EUR)));
+
+        assert_eq!(statement.lines.len(), 3);
+        assert_eq!(statement.lines[0], StatementLine {
+            date: date("2024-01-03"),
+            value_date: Some(date("2024-01-03")),
+            amount: Money::new(dec!(-600), Currency::EUR),
+            description: "Rent January".to_owned(),
+            reference: Some("REF-0001".to_owned())
+        });
+        assert_eq!(statement.lines[2].date, date("2024-01-20"));
+        assert_eq!(statement.lines[2].value_date, Some(date("2024-01-22")));
+        assert_eq!(statement.lines[2].description, "Groceries Market Street");
+        assert_eq!(statement.lines[2].reference, None);
+    }
+
+    #[test]
+    fn new_year() {
+        let line = statement_line("2401021231D10,00NMSC", Currency::EUR).unwrap();
+        assert_eq!(line.date, date("2023-12-31"));
+        assert_eq!(line.value_date, Some(date("2024-01-02")));
+
+        let reversal = statement_line("240102RCR10,00NMSC", Currency::EUR).unwrap();
+        assert_eq!(reversal.amount, Money::new(dec!(-10), Currency::EUR));
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert!(matches!(statement_line("240102€10,00NMSC", Currency::EUR), Err(StatementError::Invalid { .. })));
+        assert!(matches!(statement_line("2401é", Currency::EUR), Err(StatementError::Invalid { .. })));
+        assert!(matches!(statement_line("240102123é0D10,00", Currency::EUR), Err(StatementError::Invalid { .. })));
+    }
+
+    #[test]
+    fn unbalanced() {
+        let tampered = fixture().replace(":62F:C240131EUR2854,10", ":62F:C240131EUR2854,00");
+        assert!(matches!(parse(&tampered), Err(StatementError::Unbalanced { .. })));
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert_eq!(
+            parse(":20:X\n:61:2401030103X600,00NTRF\n").unwrap_err().to_string(),
+            "invalid MT940 statement: :61: before the opening balance"
+        );
+        assert_eq!(
+            parse(":60F:C240101XYZ1,00\n").unwrap_err().to_string(),
+            "invalid MT940 statement: unknown currency \"XYZ\""
+        );
+    }
+}
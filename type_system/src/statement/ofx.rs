@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use super::{Statement, StatementError, StatementLine};
+use crate::units::currency::Currency;
+use crate::units::money::Money;
+
+/*
+    OFX (Open Financial Exchange)
+
+    <STMTRS>
+        <CURDEF>EUR
+        <BANKACCTFROM><ACCTID>DE89...</BANKACCTFROM>
+        <STMTTRN><DTPOSTED>20240103<TRNAMT>-600.00<FITID>REF-0001<NAME>Landlord<MEMO>Rent January</STMTTRN>
+        <LEDGERBAL><BALAMT>2854.10<DTASOF>20240131</LEDGERBAL>
+    </STMTRS>
+
+    Both OFX 1 (SGML, leaf elements aren't closed) and OFX 2 (XML) are read
+    with the same tag scanner. OFX has a closing balance but no opening one,
+    so the balances of an OFX statement can't be checked.
+    <CURDEF> has to come before the first amount.
+*/
+pub fn parse(s: &str) -> Result<Vec<Statement>, StatementError> {
+    let mut statements = Vec::new();
+    let mut current: Option<Partial> = None;
+    let mut transaction: Option<Vec<(String, String)>> = None;
+    let mut balance: Option<Vec<(String, String)>> = None;
+
+    for (tag, value) in tags(s) {
+        match tag.as_str() {
+            "STMTRS" | "CCSTMTRS" => current = Some(Partial::default()),
+            "/STMTRS" | "/CCSTMTRS" => {
+                let partial = current.take().ok_or_else(|| invalid(format!("unexpected <{}>", tag)))?;
+                let mut statement = Statement::new(&partial.account, partial.currency()?);
+                statement.lines = partial.lines;
+                statement.closing = partial.closing;
+                statements.push(statement);
+            },
+            "STMTTRN" => transaction = Some(Vec::new()),
+            "/STMTTRN" => {
+                let fields = transaction.take().ok_or_else(|| invalid("unexpected </STMTTRN>".to_owned()))?;
+                let partial = current.as_mut().ok_or_else(|| invalid("transaction outside of a statement".to_owned()))?;
+                let line = statement_line(&fields, partial.currency()?)?;
+                partial.lines.push(line);
+            },
+            "LEDGERBAL" => balance = Some(Vec::new()),
+            "/LEDGERBAL" => {
+                let fields = balance.take().ok_or_else(|| invalid("unexpected </LEDGERBAL>".to_owned()))?;
+                let partial = current.as_mut().ok_or_else(|| invalid("balance outside of a statement".to_owned()))?;
+                let amount = decimal(field(&fields, "BALAMT")?)?;
+                partial.closing = Some(Money::new(amount, partial.currency()?));
+            },
+            _ => {
+                if let Some(fields) = transaction.as_mut().or(balance.as_mut()) {
+                    fields.push((tag, value));
+                } else if let Some(partial) = current.as_mut() {
+                    match tag.as_str() {
+                        "CURDEF" => {
+                            partial.currency = Some(Currency::from_code(&value)
+                                .ok_or_else(|| invalid(format!("unknown currency {:?}", value)))?);
+                        },
+                        "ACCTID" => partial.account = value,
+                        _ => {},
+                    }
+                }
+            },
+        }
+    }
+
+    if current.is_some() {
+        return Err(invalid("unclosed <STMTRS>".to_owned()));
+    }
+
+    for statement in &statements {
+        statement.validate()?;
+    }
+
+    Ok(statements)
+}
+
+// A statement being read, its currency is only known once <CURDEF> came
+#[derive(Default)]
+struct Partial {
+    account: String,
+    currency: Option<Currency>,
+    lines: Vec<StatementLine>,
+    closing: Option<Money>
+}
+
+impl Partial {
+    fn currency(&self) -> Result<Currency, StatementError> {
+        self.currency.ok_or_else(|| invalid("missing <CURDEF>".to_owned()))
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Statement>, StatementError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+// <TAG>value pairs, closing tags come as "/TAG" with no value
+fn tags(s: &str) -> Vec<(String, String)> {
+    let body = s.find('<').map_or("", |start| &s[start..]);
+
+    body.split('<').skip(1).filter_map(|part| {
+        let (tag, value) = part.split_once('>')?;
+        if tag.starts_with('?') || tag.starts_with('!') {
+            return None;
+        }
+        Some((tag.trim().to_ascii_uppercase(), value.trim().to_owned()))
+    }).collect()
+}
+
+fn statement_line(fields: &[(String, String)], currency: Currency) -> Result<StatementLine, StatementError> {
+    let get = |name: &str| fields.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+
+    let name = get("NAME").unwrap_or_default();
+    let memo = get("MEMO").unwrap_or_default();
+    let description = [name, memo].iter().filter(|s| !s.is_empty()).copied().collect::<Vec<_>>().join(" / ");
+
+    Ok(StatementLine {
+        date: date(field(fields, "DTPOSTED")?)?,
+        value_date: get("DTUSER").map(date).transpose()?,
+        amount: Money::new(decimal(field(fields, "TRNAMT")?)?, currency),
+        description,
+        reference: get("FITID").map(str::to_owned)
+    })
+}
+
+fn field<'a>(fields: &'a [(String, String)], name: &str) -> Result<&'a str, StatementError> {
+    fields.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+        .ok_or_else(|| invalid(format!("missing <{}>", name)))
+}
+
+// 20240103120000.000[+1:CET]
+fn date(s: &str) -> Result<NaiveDate, StatementError> {
+    s.get(..8)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y%m%d").ok())
+        .ok_or_else(|| invalid(format!("invalid date {:?}", s)))
+}
+
+// some banks write -600,00
+fn decimal(s: &str) -> Result<Decimal, StatementError> {
+    Decimal::from_str(&s.replace(',', ".")).map_err(|_| invalid(format!("invalid amount {:?}", s)))
+}
+
+fn invalid(message: String) -> StatementError {
+    StatementError::Invalid { format: "OFX", message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn import() {
+        let statements = load(format!("{}/fixtures/statement.ofx", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(statements.len(), 1);
+
+        let statement = &statements[0];
+        assert_eq!(statement.account, "DE89370400440532013000");
+        assert_eq!(statement.currency, Currency::EUR);
+        assert_eq!(statement.opening, None);
+        assert_eq!(statement.closing, Some(Money::new(dec!(2854.10), Currency::EUR)));
+
+        assert_eq!(statement.lines.len(), 3);
+        assert_eq!(statement.lines[0], StatementLine {
+            date: date("2024-01-03"),
+            value_date: None,
+            amount: Money::new(dec!(-600), Currency::EUR),
+            description: "Landlord / Rent January".to_owned(),
+            reference: Some("REF-0001".to_owned())
+        });
+        assert_eq!(statement.lines[2].value_date, Some(date("2024-01-22")));
+        assert_eq!(statement.total(), Ok(Money::new(dec!(1854.10), Currency::EUR)));
+    }
+
+    #[test]
+    fn xml() {
+        let ofx = "<?xml version=\"1.0\"?><OFX><STMTRS><CURDEF>USD</CURDEF>\
+            <BANKACCTFROM><ACCTID>1234</ACCTID></BANKACCTFROM>\
+            <STMTTRN><DTPOSTED>20240105</DTPOSTED><TRNAMT>12.50</TRNAMT><MEMO>Refund</MEMO></STMTTRN>\
+            </STMTRS></OFX>";
+
+        let statements = parse(ofx).unwrap();
+        assert_eq!(statements[0].account, "1234");
+        assert_eq!(statements[0].lines[0].amount, Money::new(dec!(12.5), Currency::USD));
+        assert_eq!(statements[0].lines[0].description, "Refund");
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert_eq!(
+            parse("<OFX><STMTRS><CURDEF>EUR<STMTTRN><TRNAMT>1.00</STMTTRN></STMTRS>").unwrap_err().to_string(),
+            "invalid OFX statement: missing <DTPOSTED>"
+        );
+        assert_eq!(parse("<OFX><STMTRS><CURDEF>EUR").unwrap_err().to_string(), "invalid OFX statement: unclosed <STMTRS>");
+        assert_eq!(
+            parse("<OFX><STMTRS><STMTTRN><DTPOSTED>20240105<TRNAMT>1.00</STMTTRN><CURDEF>USD</STMTRS>").unwrap_err().to_string(),
+            "invalid OFX statement: missing <CURDEF>"
+        );
+        assert_eq!(parse("<OFX><STMTRS><ACCTID>1</STMTRS>").unwrap_err().to_string(), "invalid OFX statement: missing <CURDEF>");
+    }
+}
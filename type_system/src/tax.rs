@@ -0,0 +1,422 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+
+use crate::units::currency::Currency;
+use crate::units::error::{UnitError, checked_decimal};
+use crate::units::money::Money;
+use crate::units::percentage::Percentage;
+use crate::units::rounding::RoundingPolicy;
+
+/*
+    VAT / sales tax
+
+    Net + tax = gross, always to the cent. The given amount and the
+    tax are rounded, the other side is derived from them so the three
+    never disagree. A NaN or infinite rate is an error, not 0% tax.
+
+    100€ net at 20%:   100.00€ + 20.00€ = 120.00€
+    10€ gross at 19%:    8.40€ +  1.60€ =  10.00€
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Tax {
+    pub net: Money,
+    pub tax: Money,
+    pub gross: Money
+}
+
+impl Tax {
+    // Tax exclusive: the tax is added on top of `net`
+    pub fn exclusive(net: Money, rate: Percentage) -> Result<Tax, UnitError> {
+        Tax::exclusive_with(net, rate, RoundingPolicy::default())
+    }
+
+    pub fn exclusive_with(net: Money, rate: Percentage, policy: RoundingPolicy) -> Result<Tax, UnitError> {
+        let net = net.round(policy);
+        let tax = net.amount.checked_mul(rate_of(rate)?).ok_or(UnitError::Overflow)?;
+        let tax = Money::new(tax, net.currency).round(policy);
+        Ok(Tax { net, tax, gross: net.checked_add(tax)? })
+    }
+
+    // Tax inclusive: the tax is contained in `gross`, the net is computed back from it
+    pub fn inclusive(gross: Money, rate: Percentage) -> Result<Tax, UnitError> {
+        Tax::inclusive_with(gross, rate, RoundingPolicy::default())
+    }
+
+    pub fn inclusive_with(gross: Money, rate: Percentage, policy: RoundingPolicy) -> Result<Tax, UnitError> {
+        let gross = gross.round(policy);
+        let rate = rate_of(rate)?;
+        let divisor = Decimal::ONE + rate;
+        if divisor.is_zero() {
+            return Err(UnitError::DivisionByZero);
+        }
+
+        let tax = gross.amount.checked_mul(rate).and_then(|tax| tax.checked_div(divisor)).ok_or(UnitError::Overflow)?;
+        let tax = Money::new(tax, gross.currency).round(policy);
+        Ok(Tax { net: gross.checked_sub(tax)?, tax, gross })
+    }
+
+    fn zero(currency: Currency) -> Tax {
+        let zero = Money::new(Decimal::ZERO, currency);
+        Tax { net: zero, tax: zero, gross: zero }
+    }
+
+    fn checked_add(self, rhs: Tax) -> Result<Tax, UnitError> {
+        Ok(Tax {
+            net: self.net.checked_add(rhs.net)?,
+            tax: self.tax.checked_add(rhs.tax)?,
+            gross: self.gross.checked_add(rhs.gross)?
+        })
+    }
+}
+
+// 20% -> 0.2
+fn rate_of(rate: Percentage) -> Result<Decimal, UnitError> {
+    Ok(checked_decimal(rate.value)? / Decimal::ONE_HUNDRED)
+}
+
+/*
+    Named tax rates per country and category
+
+    country,category,rate
+    DE,standard,19%
+    DE,reduced,7%
+
+    Countries are looked up case insensitively (stored upper case),
+    categories too (stored lower case).
+*/
+#[derive(Debug, Clone, Default)]
+pub struct TaxTable {
+    rates: BTreeMap<(String, String), Percentage>
+}
+
+#[derive(Debug)]
+pub enum LoadTaxTableError {
+    Io(std::io::Error),
+    Csv { line: usize, message: String }
+}
+
+impl fmt::Display for LoadTaxTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadTaxTableError::Io(e) => write!(f, "can't read tax rates: {}", e),
+            LoadTaxTableError::Csv { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LoadTaxTableError {}
+
+impl From<std::io::Error> for LoadTaxTableError {
+    fn from(e: std::io::Error) -> Self {
+        LoadTaxTableError::Io(e)
+    }
+}
+
+impl TaxTable {
+    pub fn new() -> TaxTable {
+        TaxTable::default()
+    }
+
+    pub fn insert(&mut self, country: &str, category: &str, rate: Percentage) {
+        self.rates.insert(key(country, category), rate);
+    }
+
+    pub fn with_rate(mut self, country: &str, category: &str, rate: Percentage) -> TaxTable {
+        self.insert(country, category, rate);
+        self
+    }
+
+    pub fn get(&self, country: &str, category: &str) -> Option<Percentage> {
+        self.rates.get(&key(country, category)).copied()
+    }
+
+    // Categories of a country with their rate, sorted by name
+    pub fn categories<'a>(&'a self, country: &str) -> impl Iterator<Item = (&'a str, Percentage)> + 'a {
+        let country = country.trim().to_uppercase();
+        self.rates.iter()
+            .filter(move |((c, _), _)| *c == country)
+            .map(|((_, category), &rate)| (category.as_str(), rate))
+    }
+
+    pub fn from_csv(s: &str) -> Result<TaxTable, LoadTaxTableError> {
+        let mut table = TaxTable::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("country")) {
+                continue;
+            }
+
+            let error = |message: String| LoadTaxTableError::Csv { line: i + 1, message };
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [country, category, rate] = fields[..] else {
+                return Err(error(format!("expected 3 fields, got {}", fields.len())));
+            };
+
+            if country.is_empty() || category.is_empty() {
+                return Err(error("country and category can't be empty".to_owned()));
+            }
+
+            let value = rate.strip_suffix('%').unwrap_or(rate).trim();
+            let value = f64::from_str(value).ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .ok_or_else(|| error(format!("invalid rate {:?}", rate)))?;
+
+            table.insert(country, category, Percentage::new(value));
+        }
+
+        Ok(table)
+    }
+
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> Result<TaxTable, LoadTaxTableError> {
+        TaxTable::from_csv(&fs::read_to_string(path)?)
+    }
+}
+
+fn key(country: &str, category: &str) -> (String, String) {
+    (country.trim().to_uppercase(), category.trim().to_lowercase())
+}
+
+// Are the invoice amounts before or after tax
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum Prices {
+    #[default]
+    Net,
+    Gross
+}
+
+/*
+    Where the tax is rounded
+
+    PerLine:  every line is taxed and rounded, the invoice adds them up
+    PerTotal: lines are added up per rate and each subtotal is taxed once
+
+    3 x 0.99€ at 19%: 0.57€ per line, 0.56€ per total
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum TaxMethod {
+    #[default]
+    PerLine,
+    PerTotal
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvoiceLine {
+    pub description: String,
+    pub amount: Money,
+    pub rate: Percentage
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Invoice {
+    pub currency: Currency,
+    pub prices: Prices,
+    pub method: TaxMethod,
+    pub rounding: RoundingPolicy,
+    lines: Vec<InvoiceLine>
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvoiceTotals {
+    // one per line, in order
+    pub lines: Vec<Tax>,
+    // one per rate, in order of first appearance
+    pub by_rate: Vec<(Percentage, Tax)>,
+    pub total: Tax
+}
+
+impl Invoice {
+    pub fn new(currency: Currency) -> Invoice {
+        Invoice {
+            currency,
+            prices: Prices::default(),
+            method: TaxMethod::default(),
+            rounding: RoundingPolicy::default(),
+            lines: Vec::new()
+        }
+    }
+
+    pub fn with_prices(self, prices: Prices) -> Invoice {
+        Invoice { prices, ..self }
+    }
+
+    pub fn with_method(self, method: TaxMethod) -> Invoice {
+        Invoice { method, ..self }
+    }
+
+    pub fn with_rounding(self, rounding: RoundingPolicy) -> Invoice {
+        Invoice { rounding, ..self }
+    }
+
+    pub fn line(mut self, description: &str, amount: Money, rate: Percentage) -> Invoice {
+        self.add_line(description, amount, rate);
+        self
+    }
+
+    pub fn add_line(&mut self, description: &str, amount: Money, rate: Percentage) {
+        self.lines.push(InvoiceLine { description: description.to_owned(), amount, rate });
+    }
+
+    pub fn lines(&self) -> &[InvoiceLine] {
+        &self.lines
+    }
+
+    // Fails if a line is in another currency than the invoice
+    pub fn totals(&self) -> Result<InvoiceTotals, UnitError> {
+        let lines = self.lines.iter().map(|line| self.tax(line.amount, line.rate)).collect::<Result<Vec<Tax>, _>>()?;
+
+        // subtotals of the amounts, or of the taxed lines
+        let mut by_rate: Vec<(Percentage, Tax)> = Vec::new();
+        for (line, tax) in self.lines.iter().zip(&lines) {
+            let index = match by_rate.iter().position(|(rate, _)| *rate == line.rate) {
+                Some(index) => index,
+                None => {
+                    by_rate.push((line.rate, Tax::zero(self.currency)));
+                    by_rate.len() - 1
+                },
+            };
+
+            let subtotal = &mut by_rate[index].1;
+            *subtotal = match self.method {
+                TaxMethod::PerLine => subtotal.checked_add(*tax)?,
+                TaxMethod::PerTotal => {
+                    let amount = self.amount(*subtotal).checked_add(line.amount)?;
+                    self.tax(amount, line.rate)?
+                },
+            };
+        }
+
+        let total = by_rate.iter().try_fold(Tax::zero(self.currency), |total, (_, tax)| total.checked_add(*tax))?;
+
+        Ok(InvoiceTotals { lines, by_rate, total })
+    }
+
+    fn tax(&self, amount: Money, rate: Percentage) -> Result<Tax, UnitError> {
+        match self.prices {
+            Prices::Net => Tax::exclusive_with(amount, rate, self.rounding),
+            Prices::Gross => Tax::inclusive_with(amount, rate, self.rounding),
+        }
+    }
+
+    fn amount(&self, tax: Tax) -> Money {
+        match self.prices {
+            Prices::Net => tax.net,
+            Prices::Gross => tax.gross,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::eur;
+
+    #[test]
+    fn exclusive() {
+        let tax = Tax::exclusive(eur(dec!(100)), Percentage::new(20.0)).unwrap();
+        assert_eq!(tax, Tax { net: eur(dec!(100)), tax: eur(dec!(20)), gross: eur(dec!(120)) });
+
+        let tax = Tax::exclusive(eur(dec!(0.99)), Percentage::new(19.0)).unwrap();
+        assert_eq!(tax.tax, eur(dec!(0.19)));
+        assert_eq!(tax.gross, eur(dec!(1.18)));
+
+        // sub-cent amounts are rounded before the tax is computed
+        let tax = Tax::exclusive(eur(dec!(10.004)), Percentage::new(20.0)).unwrap();
+        assert_eq!(tax, Tax { net: eur(dec!(10)), tax: eur(dec!(2)), gross: eur(dec!(12)) });
+        assert_eq!(tax.net.amount + tax.tax.amount, tax.gross.amount);
+    }
+
+    #[test]
+    fn invalid_rates() {
+        assert_eq!(Tax::exclusive(eur(dec!(10)), Percentage::new(f64::NAN)), Err(UnitError::NaN));
+        assert_eq!(Tax::inclusive(eur(dec!(10)), Percentage::new(f64::INFINITY)), Err(UnitError::Overflow));
+        assert_eq!(Tax::inclusive(eur(dec!(10)), Percentage::new(-100.0)), Err(UnitError::DivisionByZero));
+
+        let invoice = Invoice::new(Currency::EUR).line("pen", eur(dec!(1)), Percentage::new(f64::NAN));
+        assert_eq!(invoice.totals(), Err(UnitError::NaN));
+    }
+
+    #[test]
+    fn inclusive() {
+        let tax = Tax::inclusive(eur(dec!(120)), Percentage::new(20.0)).unwrap();
+        assert_eq!(tax, Tax { net: eur(dec!(100)), tax: eur(dec!(20)), gross: eur(dec!(120)) });
+
+        // not 10€ - 19%
+        let tax = Tax::inclusive(eur(dec!(10)), Percentage::new(19.0)).unwrap();
+        assert_eq!(tax.net, eur(dec!(8.40)));
+        assert_eq!(tax.tax, eur(dec!(1.60)));
+
+        let chf = Tax::inclusive_with(Money::new(dec!(50), Currency::CHF), Percentage::new(8.1), RoundingPolicy::cash(dec!(0.05))).unwrap();
+        assert_eq!(chf.tax, Money::new(dec!(3.75), Currency::CHF));
+        assert_eq!(chf.net, Money::new(dec!(46.25), Currency::CHF));
+    }
+
+    #[test]
+    fn invoice_per_line_and_per_total() {
+        let invoice = Invoice::new(Currency::EUR)
+            .line("pen", eur(dec!(0.99)), Percentage::new(19.0))
+            .line("pen", eur(dec!(0.99)), Percentage::new(19.0))
+            .line("pen", eur(dec!(0.99)), Percentage::new(19.0));
+
+        let per_line = invoice.totals().unwrap();
+        assert_eq!(per_line.lines.len(), 3);
+        assert_eq!(per_line.total.tax, eur(dec!(0.57)));
+        assert_eq!(per_line.total.gross, eur(dec!(3.54)));
+
+        let per_total = invoice.with_method(TaxMethod::PerTotal).totals().unwrap();
+        assert_eq!(per_total.total.tax, eur(dec!(0.56)));
+        assert_eq!(per_total.total.gross, eur(dec!(3.53)));
+    }
+
+    #[test]
+    fn invoice_by_rate() {
+        let totals = Invoice::new(Currency::EUR)
+            .with_prices(Prices::Gross)
+            .with_method(TaxMethod::PerTotal)
+            .line("book", eur(dec!(10.70)), Percentage::new(7.0))
+            .line("lamp", eur(dec!(59.50)), Percentage::new(19.0))
+            .line("paper", eur(dec!(21.40)), Percentage::new(7.0))
+            .totals()
+            .unwrap();
+
+        assert_eq!(totals.by_rate, vec![
+            (Percentage::new(7.0), Tax { net: eur(dec!(30)), tax: eur(dec!(2.10)), gross: eur(dec!(32.10)) }),
+            (Percentage::new(19.0), Tax { net: eur(dec!(50)), tax: eur(dec!(9.50)), gross: eur(dec!(59.50)) }),
+        ]);
+        assert_eq!(totals.total, Tax { net: eur(dec!(80)), tax: eur(dec!(11.60)), gross: eur(dec!(91.60)) });
+    }
+
+    #[test]
+    fn invoice_currency_mismatch() {
+        let invoice = Invoice::new(Currency::EUR).line("fee", Money::new(dec!(5), Currency::USD), Percentage::new(20.0));
+        assert!(matches!(invoice.totals(), Err(UnitError::IncompatibleUnits(_))));
+    }
+
+    #[test]
+    fn table() {
+        let table = TaxTable::load_csv(format!("{}/fixtures/tax_rates.csv", env!("CARGO_MANIFEST_DIR"))).unwrap();
+
+        assert_eq!(table.get("DE", "standard"), Some(Percentage::new(19.0)));
+        assert_eq!(table.get("fr", "Reduced"), Some(Percentage::new(5.5)));
+        assert_eq!(table.get("DE", "zero"), None);
+        assert_eq!(
+            table.categories("CH").map(|(category, _)| category).collect::<Vec<_>>(),
+            vec!["accommodation", "reduced", "standard"]
+        );
+    }
+
+    #[test]
+    fn table_errors() {
+        let error = TaxTable::from_csv("country,category,rate\nDE,standard\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: expected 3 fields, got 2");
+
+        let error = TaxTable::from_csv("DE,standard,lots").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: invalid rate \"lots\"");
+    }
+}
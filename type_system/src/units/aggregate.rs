@@ -0,0 +1,219 @@
+use std::iter::{Product, Sum};
+use std::ops::{Add, Mul};
+use rust_decimal::Decimal;
+
+use super::currency::Currency;
+use super::error::UnitError;
+use super::money::Money;
+use super::moneybag::MoneyBag;
+use super::restype::ResType;
+use crate::Percentage;
+
+/*
+    Sums, products and statistics
+
+    Money can't be summed into a Money: an empty list has no currency
+    for its zero. Sum into a `Result<Money, UnitError>` (Empty for no
+    values, IncompatibleUnits for mixed currencies), use `try_sum` to
+    start from zero in a given currency, or sum into a MoneyBag, which
+    accepts any mix of currencies and is empty for nothing.
+
+    Money has no `Product`: 2€ * 3€ means nothing.
+*/
+impl Money {
+    // Zero in `currency` when there is nothing to add
+    pub fn try_sum<I: IntoIterator<Item = Money>>(currency: Currency, iter: I) -> Result<Money, UnitError> {
+        iter.into_iter().try_fold(Money::new(Decimal::ZERO, currency), Money::checked_add)
+    }
+
+    pub fn mean(values: &[Money]) -> Result<Money, UnitError> {
+        let first = values.first().ok_or(UnitError::Empty)?;
+        let total = Money::try_sum(first.currency, values.iter().copied())?;
+        total.checked_div(Decimal::from(values.len()))
+    }
+
+    // Middle value, or the mean of the two middle values
+    pub fn median(values: &[Money]) -> Result<Money, UnitError> {
+        let mut sorted = same_currency(values)?;
+        sorted.sort_by_key(|money| money.amount);
+
+        let middle = sorted.len() / 2;
+        if sorted.len() % 2 == 1 {
+            Ok(sorted[middle])
+        } else {
+            Money::mean(&sorted[middle - 1..=middle])
+        }
+    }
+
+    pub fn min(values: &[Money]) -> Result<Money, UnitError> {
+        same_currency(values)?.into_iter().min_by_key(|money| money.amount).ok_or(UnitError::Empty)
+    }
+
+    pub fn max(values: &[Money]) -> Result<Money, UnitError> {
+        same_currency(values)?.into_iter().max_by_key(|money| money.amount).ok_or(UnitError::Empty)
+    }
+}
+
+// Errors on an empty list or on mixed currencies
+fn same_currency(values: &[Money]) -> Result<Vec<Money>, UnitError> {
+    let first = values.first().ok_or(UnitError::Empty)?;
+
+    if let Some(other) = values.iter().find(|money| money.currency != first.currency) {
+        return Err(UnitError::IncompatibleUnits(format!("{} and {}", first, other)));
+    }
+
+    Ok(values.to_vec())
+}
+
+impl Sum<Money> for Result<Money, UnitError> {
+    fn sum<I: Iterator<Item = Money>>(mut iter: I) -> Result<Money, UnitError> {
+        let first = iter.next().ok_or(UnitError::Empty)?;
+        iter.try_fold(first, Money::checked_add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Result<Money, UnitError> {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Result<Money, UnitError> {
+        iter.copied().sum()
+    }
+}
+
+impl Sum<Money> for MoneyBag {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> MoneyBag {
+        iter.collect()
+    }
+}
+
+impl<'a> Sum<&'a Money> for MoneyBag {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> MoneyBag {
+        iter.copied().collect()
+    }
+}
+
+// Percentages: nothing adds up to 0%, the empty product is 100%
+// (50% of 10% of 100% is 5%)
+impl Percentage {
+    pub fn try_sum<I: IntoIterator<Item = Percentage>>(iter: I) -> Result<Percentage, UnitError> {
+        iter.into_iter().try_fold(Percentage::new(0.0), Percentage::checked_add)
+    }
+
+    pub fn try_product<I: IntoIterator<Item = Percentage>>(iter: I) -> Result<Percentage, UnitError> {
+        iter.into_iter().try_fold(Percentage::new(100.0), Percentage::checked_mul)
+    }
+}
+
+impl Sum for Percentage {
+    fn sum<I: Iterator<Item = Percentage>>(iter: I) -> Percentage {
+        iter.fold(Percentage::new(0.0), Add::add)
+    }
+}
+
+impl Product for Percentage {
+    fn product<I: Iterator<Item = Percentage>>(iter: I) -> Percentage {
+        iter.fold(Percentage::new(100.0), Mul::mul)
+    }
+}
+
+// ResType: nothing adds up to 0 and multiplies to 1
+impl ResType {
+    pub fn try_sum<I: IntoIterator<Item = ResType>>(iter: I) -> Result<ResType, UnitError> {
+        let mut iter = iter.into_iter();
+        match iter.next() {
+            Some(first) => iter.try_fold(first, ResType::checked_add),
+            None => Ok(ResType::Int(0)),
+        }
+    }
+
+    pub fn try_product<I: IntoIterator<Item = ResType>>(iter: I) -> Result<ResType, UnitError> {
+        let mut iter = iter.into_iter();
+        match iter.next() {
+            Some(first) => iter.try_fold(first, ResType::checked_mul),
+            None => Ok(ResType::Int(1)),
+        }
+    }
+}
+
+impl Sum for ResType {
+    fn sum<I: Iterator<Item = ResType>>(iter: I) -> ResType {
+        iter.reduce(Add::add).unwrap_or(ResType::Int(0))
+    }
+}
+
+impl Product for ResType {
+    fn product<I: Iterator<Item = ResType>>(iter: I) -> ResType {
+        iter.reduce(Mul::mul).unwrap_or(ResType::Int(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::{eur, usd};
+
+    #[test]
+    fn sum_money() {
+        let prices = vec![eur(dec!(10)), eur(dec!(2.5)), eur(dec!(0.5))];
+        assert_eq!(prices.iter().sum::<Result<Money, UnitError>>(), Ok(eur(dec!(13))));
+        assert_eq!(prices.into_iter().sum::<Result<Money, UnitError>>(), Ok(eur(dec!(13))));
+
+        assert_eq!(Money::try_sum(Currency::EUR, vec![]), Ok(eur(dec!(0))));
+        assert!(matches!(
+            Money::try_sum(Currency::EUR, vec![eur(dec!(1)), usd(dec!(1))]),
+            Err(UnitError::IncompatibleUnits(_))
+        ));
+
+        let bag: MoneyBag = [eur(dec!(1)), usd(dec!(2)), eur(dec!(3))].iter().sum();
+        assert_eq!(bag, MoneyBag::from(eur(dec!(4))) + usd(dec!(2)));
+    }
+
+    #[test]
+    fn sum_nothing_or_a_mix() {
+        let total: Result<Money, UnitError> = Vec::<Money>::new().into_iter().sum();
+        assert_eq!(total, Err(UnitError::Empty));
+
+        let total: Result<Money, UnitError> = [eur(dec!(1)), usd(dec!(2))].iter().sum();
+        assert!(matches!(total, Err(UnitError::IncompatibleUnits(_))));
+
+        assert_eq!(Vec::<Money>::new().into_iter().sum::<MoneyBag>(), MoneyBag::new());
+    }
+
+    #[test]
+    fn percentages() {
+        let rates = [Percentage::new(10.0), Percentage::new(2.5)];
+        assert_eq!(rates.into_iter().sum::<Percentage>(), Percentage::new(12.5));
+        assert_eq!(Vec::<Percentage>::new().into_iter().sum::<Percentage>(), Percentage::new(0.0));
+        assert_eq!(Percentage::try_sum([Percentage::new(f64::MAX), Percentage::new(f64::MAX)]), Err(UnitError::Overflow));
+
+        let shares = [Percentage::new(50.0), Percentage::new(10.0)];
+        assert_eq!(shares.into_iter().product::<Percentage>(), Percentage::new(5.0));
+        assert_eq!(Vec::<Percentage>::new().into_iter().product::<Percentage>(), Percentage::new(100.0));
+        assert_eq!(Percentage::try_product(shares), Ok(Percentage::new(5.0)));
+        assert_eq!(Percentage::try_product([Percentage::new(f64::MAX), Percentage::new(1000.0)]), Err(UnitError::Overflow));
+    }
+
+    #[test]
+    fn restypes() {
+        let values = vec![ResType::Int(2), ResType::Float(0.5), ResType::Int(3)];
+        assert_eq!(values.clone().into_iter().sum::<ResType>(), ResType::Float(5.5));
+        assert_eq!(values.into_iter().product::<ResType>(), ResType::Float(3.0));
+        assert_eq!(Vec::<ResType>::new().into_iter().sum::<ResType>(), ResType::Int(0));
+
+        let money = vec![ResType::Money(eur(dec!(10))), ResType::Money(usd(dec!(5)))];
+        assert_eq!(ResType::try_sum(money), Ok(ResType::Bag(MoneyBag::from(eur(dec!(10))) + usd(dec!(5)))));
+        assert!(ResType::try_product(vec![ResType::Money(eur(dec!(1))), ResType::Money(eur(dec!(2)))]).is_err());
+    }
+
+    #[test]
+    fn statistics() {
+        let prices = [eur(dec!(4)), eur(dec!(1)), eur(dec!(10)), eur(dec!(3))];
+        assert_eq!(Money::mean(&prices), Ok(eur(dec!(4.5))));
+        assert_eq!(Money::median(&prices), Ok(eur(dec!(3.5))));
+        assert_eq!(Money::median(&prices[..3]), Ok(eur(dec!(4))));
+        assert_eq!(Money::min(&prices), Ok(eur(dec!(1))));
+        assert_eq!(Money::max(&prices), Ok(eur(dec!(10))));
+
+        assert_eq!(Money::mean(&[]), Err(UnitError::Empty));
+        assert!(matches!(Money::max(&[eur(dec!(1)), usd(dec!(2))]), Err(UnitError::IncompatibleUnits(_))));
+    }
+}
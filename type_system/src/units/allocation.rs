@@ -0,0 +1,140 @@
+use std::fmt;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::money::Money;
+use super::rounding::RoundingPolicy;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum AllocationError {
+    NoParts,
+    NegativeWeight,
+    // a NaN or infinite percentage
+    InvalidWeight,
+    ZeroWeights
+}
+
+impl fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocationError::NoParts => write!(f, "can't allocate money to zero parts"),
+            AllocationError::NegativeWeight => write!(f, "allocation weights can't be negative"),
+            AllocationError::InvalidWeight => write!(f, "allocation weights must be finite numbers"),
+            AllocationError::ZeroWeights => write!(f, "allocation weights sum to zero"),
+        }
+    }
+}
+
+impl std::error::Error for AllocationError {}
+
+/*
+    Lossless allocation
+
+    The amount is cut in minor units (cents), every part gets its
+    share rounded down, and the cents left over go one by one to
+    the parts that lost the most to rounding (the first one on a tie).
+    The parts always sum to the original amount.
+
+    10€ split in 3: [3.34€, 3.33€, 3.33€]
+*/
+impl Money {
+    pub fn split(self, parts: usize) -> Result<Vec<Money>, AllocationError> {
+        self.allocate(&vec![Decimal::ONE; parts])
+    }
+
+    // Weights are relative: [1, 1, 2], [25%, 25%, 50%] and [0.25, 0.25, 0.5] are the same
+    pub fn allocate<W: Copy + TryInto<Decimal>>(self, weights: &[W]) -> Result<Vec<Money>, AllocationError> {
+        let weights = weights.iter()
+            .map(|&w| w.try_into().map_err(|_| AllocationError::InvalidWeight))
+            .collect::<Result<Vec<Decimal>, _>>()?;
+
+        if weights.is_empty() {
+            return Err(AllocationError::NoParts);
+        }
+        if weights.iter().any(|w| w.is_sign_negative() && !w.is_zero()) {
+            return Err(AllocationError::NegativeWeight);
+        }
+
+        let total_weight: Decimal = weights.iter().sum();
+        if total_weight.is_zero() {
+            return Err(AllocationError::ZeroWeights);
+        }
+
+        let unit = Decimal::new(1, u32::from(self.currency.minor_units));
+        let amount = self.round(RoundingPolicy::default()).amount;
+        let units = (amount / unit).abs();
+
+        let exact: Vec<Decimal> = weights.iter().map(|w| units * w / total_weight).collect();
+        let mut shares: Vec<Decimal> = exact.iter().map(|share| share.trunc()).collect();
+
+        let left_over: Decimal = units - shares.iter().sum::<Decimal>();
+
+        let mut by_loss: Vec<usize> = (0..shares.len()).collect();
+        by_loss.sort_by(|&a, &b| (exact[b] - shares[b]).cmp(&(exact[a] - shares[a])));
+
+        for &i in by_loss.iter().take(left_over.to_usize().unwrap_or(0)) {
+            shares[i] += Decimal::ONE;
+        }
+
+        let sign = if amount.is_sign_negative() { -Decimal::ONE } else { Decimal::ONE };
+        Ok(shares.into_iter()
+            .map(|share| Money::new(share * unit * sign, self.currency))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::Percentage;
+    use crate::units::currency::Currency;
+
+    fn euros(amounts: &[Decimal]) -> Vec<Money> {
+        amounts.iter().map(|&a| Money::new(a, Currency::EUR)).collect()
+    }
+
+    #[test]
+    fn split() {
+        let bill = Money::new(dec!(10), Currency::EUR);
+        assert_eq!(bill.split(3), Ok(euros(&[dec!(3.34), dec!(3.33), dec!(3.33)])));
+        assert_eq!(bill.split(0), Err(AllocationError::NoParts));
+
+        let refund = Money::new(dec!(-0.05), Currency::EUR);
+        assert_eq!(refund.split(2), Ok(euros(&[dec!(-0.03), dec!(-0.02)])));
+    }
+
+    #[test]
+    fn allocate() {
+        let discount = Money::new(dec!(0.05), Currency::EUR);
+        assert_eq!(discount.allocate(&[3, 7]), Ok(euros(&[dec!(0.02), dec!(0.03)])));
+
+        // the biggest rounding loss gets the left over cent
+        let total = Money::new(dec!(100), Currency::EUR);
+        assert_eq!(total.allocate(&[1, 2]), Ok(euros(&[dec!(33.33), dec!(66.67)])));
+        assert_eq!(total.allocate(&[1, 1, 1]), Ok(euros(&[dec!(33.34), dec!(33.33), dec!(33.33)])));
+    }
+
+    #[test]
+    fn allocate_percentages() {
+        let yen = Money::new(dec!(1001), Currency::JPY);
+        let parts = yen.allocate(&[Percentage::new(20.0), Percentage::new(30.0), Percentage::new(50.0)]).unwrap();
+
+        assert_eq!(parts.iter().map(|m| m.amount).sum::<Decimal>(), dec!(1001));
+        assert_eq!(parts, vec![
+            Money::new(dec!(200), Currency::JPY),
+            Money::new(dec!(300), Currency::JPY),
+            Money::new(dec!(501), Currency::JPY),
+        ]);
+    }
+
+    #[test]
+    fn invalid_weights() {
+        let money = Money::new(dec!(1), Currency::EUR);
+        assert_eq!(money.allocate(&[1, -1]), Err(AllocationError::NegativeWeight));
+        assert_eq!(money.allocate(&[0, 0]), Err(AllocationError::ZeroWeights));
+        assert_eq!(money.allocate::<i32>(&[]), Err(AllocationError::NoParts));
+        assert_eq!(money.allocate(&[Percentage::new(50.0), Percentage::new(f64::NAN)]), Err(AllocationError::InvalidWeight));
+        assert_eq!(money.allocate(&[Percentage::new(f64::INFINITY)]), Err(AllocationError::InvalidWeight));
+    }
+}
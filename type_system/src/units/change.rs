@@ -0,0 +1,228 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::error::{UnitError, checked_decimal, finite};
+use super::money::Money;
+use super::restype::ResType;
+use super::rounding::RoundingPolicy;
+use crate::Percentage;
+
+/*
+    Percentages the other way around
+
+    20€ is 25% of 80€           part.percent_of(whole)
+    20€ is 25% of what? 80€     part.base(25%)
+    80€ -> 100€ is +25%         old.change_to(new)
+    100€ -> 121€ in 2 years     Money::cagr(start, end, 2.0) = 10% a year
+    120€ after +20% was 100€    price.undo_markup(20%)
+
+    The same exists for plain numbers on Percentage, and on ResType for
+    the calculator. Money results are rounded with the default policy,
+    percentages are not rounded.
+*/
+impl Money {
+    pub fn percent_of(self, whole: Money) -> Result<Percentage, UnitError> {
+        same_currency(self, whole)?;
+        let ratio = self.amount.checked_div(nonzero(whole.amount)?).ok_or(UnitError::Overflow)?;
+        percentage(ratio)
+    }
+
+    // The whole `self` is `percentage` of
+    pub fn base(self, percentage: Percentage) -> Result<Money, UnitError> {
+        let rate = nonzero(rate(percentage)?)?;
+        let amount = self.amount.checked_div(rate).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency).round(RoundingPolicy::default()))
+    }
+
+    // Relative change from `self` to `new`
+    pub fn change_to(self, new: Money) -> Result<Percentage, UnitError> {
+        same_currency(self, new)?;
+        let difference = new.amount.checked_sub(self.amount).ok_or(UnitError::Overflow)?;
+        let ratio = difference.checked_div(nonzero(self.amount)?).ok_or(UnitError::Overflow)?;
+        percentage(ratio)
+    }
+
+    // Compound annual growth rate from `start` to `end` over `years`
+    pub fn cagr(start: Money, end: Money, years: f64) -> Result<Percentage, UnitError> {
+        same_currency(start, end)?;
+        let ratio = end.amount.checked_div(nonzero(start.amount)?).ok_or(UnitError::Overflow)?;
+        Percentage::cagr(1.0, ratio.to_f64().ok_or(UnitError::Overflow)?, years)
+    }
+
+    // The price before `markup` was added, a negative markup undoes a discount
+    pub fn undo_markup(self, markup: Percentage) -> Result<Money, UnitError> {
+        let factor = nonzero(Decimal::ONE + rate(markup)?)?;
+        let amount = self.amount.checked_div(factor).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency).round(RoundingPolicy::default()))
+    }
+}
+
+impl Percentage {
+    // 20 is 25% of 80
+    pub fn of(part: f64, whole: f64) -> Result<Percentage, UnitError> {
+        if whole == 0.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        Ok(Percentage::new(finite(part / whole * 100.0)?))
+    }
+
+    // 80 -> 100 is +25%
+    pub fn change(old: f64, new: f64) -> Result<Percentage, UnitError> {
+        if old == 0.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        Ok(Percentage::new(finite((new - old) / old * 100.0)?))
+    }
+
+    // A shrinking value gives a negative rate, a change of sign has none
+    pub fn cagr(start: f64, end: f64, years: f64) -> Result<Percentage, UnitError> {
+        if start == 0.0 || years == 0.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        Ok(Percentage::new(finite(((end / start).powf(1.0 / years) - 1.0) * 100.0)?))
+    }
+
+    // 20 is 25% of 80
+    pub fn base(self, part: f64) -> Result<f64, UnitError> {
+        if self.value == 0.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        finite(part / self.value * 100.0)
+    }
+
+    // 120 after +20% was 100
+    pub fn undo(self, value: f64) -> Result<f64, UnitError> {
+        if self.value == -100.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        finite(value / (1.0 + self.value / 100.0))
+    }
+
+    // 5% -> 6% is +20%, not the +1 point of 6% - 5%
+    pub fn change_to(self, new: Percentage) -> Result<Percentage, UnitError> {
+        Percentage::change(self.value, new.value)
+    }
+}
+
+// Amounts or plain numbers, the result has the unit of the question
+impl ResType {
+    // What percent of `whole` is `self`
+    pub fn percent_of(self, whole: ResType) -> Result<ResType, UnitError> {
+        match (&self, &whole) {
+            (ResType::Money(part), ResType::Money(whole)) => Ok(ResType::Percent(part.percent_of(*whole)?)),
+            _ => Ok(ResType::Percent(Percentage::of(number(&self, &whole)?, number(&whole, &self)?)?)),
+        }
+    }
+
+    pub fn change_to(self, new: ResType) -> Result<ResType, UnitError> {
+        match (&self, &new) {
+            (ResType::Money(old), ResType::Money(new)) => Ok(ResType::Percent(old.change_to(*new)?)),
+            _ => Ok(ResType::Percent(Percentage::change(number(&self, &new)?, number(&new, &self)?)?)),
+        }
+    }
+
+    // `self` is `percentage` of what
+    pub fn base(self, percentage: Percentage) -> Result<ResType, UnitError> {
+        match self {
+            ResType::Money(part) => Ok(ResType::Money(part.base(percentage)?)),
+            other => Ok(ResType::Float(percentage.base(number(&other, &ResType::Percent(percentage))?)?)),
+        }
+    }
+
+    pub fn undo_markup(self, markup: Percentage) -> Result<ResType, UnitError> {
+        match self {
+            ResType::Money(price) => Ok(ResType::Money(price.undo_markup(markup)?)),
+            other => Ok(ResType::Float(markup.undo(number(&other, &ResType::Percent(markup))?)?)),
+        }
+    }
+}
+
+// The plain number in `value`, `other` is only there for the error
+fn number(value: &ResType, other: &ResType) -> Result<f64, UnitError> {
+    value.number().ok_or_else(|| UnitError::IncompatibleUnits(format!("{} and {}", value, other)))
+}
+
+fn same_currency(a: Money, b: Money) -> Result<(), UnitError> {
+    if a.currency != b.currency {
+        return Err(UnitError::IncompatibleUnits(format!("{} and {}", a, b)));
+    }
+    Ok(())
+}
+
+fn nonzero(value: Decimal) -> Result<Decimal, UnitError> {
+    if value.is_zero() {
+        return Err(UnitError::DivisionByZero);
+    }
+    Ok(value)
+}
+
+// 12% -> 0.12
+fn rate(percentage: Percentage) -> Result<Decimal, UnitError> {
+    Ok(checked_decimal(percentage.value)? / Decimal::ONE_HUNDRED)
+}
+
+// 0.25 -> 25%
+fn percentage(ratio: Decimal) -> Result<Percentage, UnitError> {
+    let value = ratio.checked_mul(Decimal::ONE_HUNDRED).ok_or(UnitError::Overflow)?;
+    Ok(Percentage::new(value.to_f64().ok_or(UnitError::Overflow)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::{eur, usd};
+
+    #[test]
+    fn money() {
+        assert_eq!(eur(dec!(20)).percent_of(eur(dec!(80))), Ok(Percentage::new(25.0)));
+        assert_eq!(eur(dec!(20)).base(Percentage::new(25.0)), Ok(eur(dec!(80))));
+        assert_eq!(eur(dec!(80)).change_to(eur(dec!(100))), Ok(Percentage::new(25.0)));
+        assert_eq!(eur(dec!(100)).change_to(eur(dec!(80))), Ok(Percentage::new(-20.0)));
+        assert_eq!(eur(dec!(120)).undo_markup(Percentage::new(20.0)), Ok(eur(dec!(100))));
+        assert_eq!(eur(dec!(80)).undo_markup(Percentage::new(-20.0)), Ok(eur(dec!(100))));
+        assert_eq!(eur(dec!(10)).undo_markup(Percentage::new(19.0)), Ok(eur(dec!(8.40))));
+
+        let cagr = Money::cagr(eur(dec!(100)), eur(dec!(121)), 2.0).unwrap();
+        assert!((cagr.value - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn money_errors() {
+        assert_eq!(eur(dec!(20)).percent_of(eur(dec!(0))), Err(UnitError::DivisionByZero));
+        assert_eq!(eur(dec!(0)).change_to(eur(dec!(10))), Err(UnitError::DivisionByZero));
+        assert_eq!(eur(dec!(20)).base(Percentage::new(0.0)), Err(UnitError::DivisionByZero));
+        assert_eq!(eur(dec!(20)).undo_markup(Percentage::new(-100.0)), Err(UnitError::DivisionByZero));
+        assert!(matches!(eur(dec!(20)).percent_of(usd(dec!(80))), Err(UnitError::IncompatibleUnits(_))));
+        assert!(matches!(Money::cagr(eur(dec!(1)), usd(dec!(2)), 1.0), Err(UnitError::IncompatibleUnits(_))));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(Percentage::of(20.0, 80.0), Ok(Percentage::new(25.0)));
+        assert_eq!(Percentage::new(25.0).base(20.0), Ok(80.0));
+        assert_eq!(Percentage::change(80.0, 100.0), Ok(Percentage::new(25.0)));
+        assert_eq!(Percentage::new(20.0).undo(120.0), Ok(100.0));
+        assert_eq!(Percentage::cagr(100.0, 50.0, 1.0), Ok(Percentage::new(-50.0)));
+        assert_eq!(Percentage::new(5.0).change_to(Percentage::new(6.0)).map(|p| p.value.round()), Ok(20.0));
+
+        assert_eq!(Percentage::of(1.0, 0.0), Err(UnitError::DivisionByZero));
+        assert_eq!(Percentage::cagr(100.0, 121.0, 0.0), Err(UnitError::DivisionByZero));
+        assert_eq!(Percentage::cagr(100.0, -10.0, 2.0), Err(UnitError::NaN));
+    }
+
+    #[test]
+    fn calculator() {
+        let money = |amount| ResType::Money(eur(amount));
+        assert_eq!(money(dec!(20)).percent_of(money(dec!(80))), Ok(ResType::Percent(Percentage::new(25.0))));
+        assert_eq!(ResType::Int(20).percent_of(ResType::Float(80.0)), Ok(ResType::Percent(Percentage::new(25.0))));
+        assert_eq!(money(dec!(80)).change_to(money(dec!(100))), Ok(ResType::Percent(Percentage::new(25.0))));
+        assert_eq!(money(dec!(20)).base(Percentage::new(25.0)), Ok(money(dec!(80))));
+        assert_eq!(ResType::Int(20).base(Percentage::new(25.0)), Ok(ResType::Float(80.0)));
+        assert_eq!(money(dec!(120)).undo_markup(Percentage::new(20.0)), Ok(money(dec!(100))));
+
+        assert!(matches!(money(dec!(20)).percent_of(ResType::Int(80)), Err(UnitError::IncompatibleUnits(_))));
+        assert!(matches!(ResType::Percent(Percentage::new(5.0)).base(Percentage::new(5.0)), Err(UnitError::IncompatibleUnits(_))));
+        assert_eq!(eur(Decimal::MIN).change_to(eur(Decimal::MAX)), Err(UnitError::Overflow));
+    }
+}
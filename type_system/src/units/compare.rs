@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+use rust_decimal::Decimal;
+
+use super::context::Context;
+use super::currency::Currency;
+use super::error::UnitError;
+use super::exchange::{ExchangeError, ExchangeRateProvider};
+use super::money::Money;
+
+/*
+    Comparing amounts by value
+
+    Money has no `Ord`: 10€ and $20 have no order without exchange rates.
+    Either both amounts are in the same currency, or one is converted
+    with exchange rates. Converted values are compared before rounding,
+    a conversion too large for a Decimal is an overflow.
+
+    `cmp_by_currency` is a total order, currency first, to sort or group
+    mixed amounts. It says nothing about 10€ < $20.
+*/
+impl Money {
+    pub fn cmp_by_currency(&self, other: &Money) -> Ordering {
+        self.currency.cmp(&other.currency)
+            .then_with(|| self.amount.cmp(&other.amount))
+    }
+
+    // Fails on different currencies
+    pub fn try_cmp(&self, other: &Money) -> Result<Ordering, UnitError> {
+        if self.currency != other.currency {
+            return Err(UnitError::IncompatibleUnits(format!("{} <=> {}", self, other)));
+        }
+        Ok(self.amount.cmp(&other.amount))
+    }
+
+    // `other` is converted to the currency of `self`
+    pub fn cmp_with(&self, other: &Money, rates: &dyn ExchangeRateProvider) -> Result<Ordering, UnitError> {
+        Ok(self.amount.cmp(&value_in(*other, self.currency, rates)?))
+    }
+
+    // Same currency and at most `tolerance` apart
+    pub fn approx_eq(&self, other: &Money, tolerance: Decimal) -> bool {
+        self.currency == other.currency && within(self.amount, other.amount, tolerance)
+    }
+
+    // 10€ and $10.87 are equal within 0.01 at 1.087
+    pub fn approx_eq_with(&self, other: &Money, rates: &dyn ExchangeRateProvider, tolerance: Decimal) -> Result<bool, UnitError> {
+        Ok(within(self.amount, value_in(*other, self.currency, rates)?, tolerance))
+    }
+}
+
+// A difference too large for a Decimal is not within any tolerance
+fn within(a: Decimal, b: Decimal, tolerance: Decimal) -> bool {
+    a.checked_sub(b).is_some_and(|difference| difference.abs() <= tolerance)
+}
+
+// Unrounded value of `money` in `currency`
+fn value_in(money: Money, currency: Currency, rates: &dyn ExchangeRateProvider) -> Result<Decimal, UnitError> {
+    if money.currency == currency {
+        return Ok(money.amount);
+    }
+    money.amount.checked_mul(rates.rate(money.currency, currency)?).ok_or(UnitError::Overflow)
+}
+
+impl Context<'_> {
+    // `b` is converted to the currency of `a`
+    pub fn cmp(&self, a: Money, b: Money) -> Result<Ordering, UnitError> {
+        Ok(a.amount.cmp(&self.value_in(b, a.currency)?))
+    }
+
+    pub fn approx_eq(&self, a: Money, b: Money, tolerance: Decimal) -> Result<bool, UnitError> {
+        Ok(within(a.amount, self.value_in(b, a.currency)?, tolerance))
+    }
+
+    // Cheapest first, by value in `currency`. Equal values keep their order.
+    pub fn sort(&self, prices: &mut [Money], currency: Currency) -> Result<(), UnitError> {
+        let mut keyed = prices.iter()
+            .map(|&price| Ok((self.value_in(price, currency)?, price)))
+            .collect::<Result<Vec<_>, UnitError>>()?;
+
+        keyed.sort_by_key(|&(value, _)| value);
+
+        for (slot, (_, price)) in prices.iter_mut().zip(keyed) {
+            *slot = price;
+        }
+        Ok(())
+    }
+
+    // The first of the cheapest offers, None for no offers
+    pub fn cheapest(&self, prices: &[Money]) -> Result<Option<Money>, UnitError> {
+        let Some(first) = prices.first() else {
+            return Ok(None);
+        };
+
+        let mut cheapest = (first.amount, *first);
+        for &price in &prices[1..] {
+            let value = self.value_in(price, first.currency)?;
+            if value < cheapest.0 {
+                cheapest = (value, price);
+            }
+        }
+
+        Ok(Some(cheapest.1))
+    }
+
+    fn value_in(&self, money: Money, currency: Currency) -> Result<Decimal, UnitError> {
+        match self.rates() {
+            Some(rates) => value_in(money, currency, rates),
+            None if money.currency == currency => Ok(money.amount),
+            None => Err(ExchangeError::NoRate { from: money.currency, to: currency }.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::RateTable;
+    use crate::units::testing::{eur, usd};
+
+    fn rates() -> RateTable {
+        RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.087))
+    }
+
+    #[test]
+    fn same_currency() {
+        assert_eq!(eur(dec!(10)).try_cmp(&eur(dec!(20))), Ok(Ordering::Less));
+        assert!(matches!(eur(dec!(10)).try_cmp(&usd(dec!(20))), Err(UnitError::IncompatibleUnits(_))));
+
+        assert!(eur(dec!(10)).approx_eq(&eur(dec!(10.004)), dec!(0.005)));
+        assert!(!eur(dec!(10)).approx_eq(&usd(dec!(10)), dec!(1)));
+        assert!(!eur(Decimal::MAX).approx_eq(&eur(Decimal::MIN), dec!(1)));
+    }
+
+    #[test]
+    fn by_currency() {
+        assert_eq!(eur(dec!(10)).cmp_by_currency(&usd(dec!(1))), Ordering::Less);
+        assert_eq!(usd(dec!(1)).cmp_by_currency(&eur(dec!(10))), Ordering::Greater);
+        assert_eq!(eur(dec!(2)).cmp_by_currency(&eur(dec!(10))), Ordering::Less);
+    }
+
+    #[test]
+    fn overflow() {
+        let rates = rates();
+        assert_eq!(eur(dec!(10)).cmp_with(&usd(Decimal::MAX), &rates), Ok(Ordering::Less));
+        assert_eq!(usd(dec!(10)).cmp_with(&eur(Decimal::MAX), &rates), Err(UnitError::Overflow));
+        assert_eq!(usd(dec!(10)).approx_eq_with(&eur(Decimal::MAX), &rates, dec!(0.01)), Err(UnitError::Overflow));
+        assert_eq!(Context::new(&rates).cheapest(&[usd(dec!(1)), eur(Decimal::MAX)]), Err(UnitError::Overflow));
+    }
+
+    #[test]
+    fn across_currencies() {
+        let rates = rates();
+        assert_eq!(eur(dec!(10)).cmp_with(&usd(dec!(20)), &rates), Ok(Ordering::Less));
+        assert_eq!(eur(dec!(10)).cmp_with(&usd(dec!(10.80)), &rates), Ok(Ordering::Greater));
+        assert_eq!(usd(dec!(10.80)).cmp_with(&eur(dec!(10)), &rates), Ok(Ordering::Less));
+
+        assert_eq!(eur(dec!(10)).approx_eq_with(&usd(dec!(10.87)), &rates, dec!(0.01)), Ok(true));
+        assert_eq!(eur(dec!(10)).approx_eq_with(&usd(dec!(10.80)), &rates, dec!(0.01)), Ok(false));
+
+        let gbp = Money::new(dec!(1), Currency::GBP);
+        assert!(eur(dec!(1)).cmp_with(&gbp, &rates).is_err());
+    }
+
+    #[test]
+    fn price_list() {
+        let rates = rates();
+        let ctx = Context::new(&rates);
+
+        let mut prices = vec![eur(dec!(10)), usd(dec!(10)), eur(dec!(9.5)), usd(dec!(11))];
+        ctx.sort(&mut prices, Currency::EUR).unwrap();
+        assert_eq!(prices, vec![usd(dec!(10)), eur(dec!(9.5)), eur(dec!(10)), usd(dec!(11))]);
+
+        assert_eq!(ctx.cheapest(&[eur(dec!(10)), usd(dec!(10.9)), eur(dec!(9.8))]), Ok(Some(eur(dec!(9.8)))));
+        assert_eq!(ctx.cheapest(&[]), Ok(None));
+        assert_eq!(ctx.cmp(usd(dec!(11)), eur(dec!(10))), Ok(Ordering::Greater));
+
+        assert_eq!(
+            Context::default().cheapest(&[eur(dec!(1)), usd(dec!(1))]),
+            Err(UnitError::UnknownConversion(ExchangeError::NoRate { from: Currency::USD, to: Currency::EUR }))
+        );
+    }
+}
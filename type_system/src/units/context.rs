@@ -0,0 +1,117 @@
+use super::currency::Currency;
+use super::exchange::{ExchangeError, ExchangeRateProvider};
+use super::money::Money;
+use super::rounding::RoundingPolicy;
+use crate::Percentage;
+
+/*
+    Calculation context
+
+    Everything a calculation needs besides its operands:
+    the exchange rates and the rounding policy.
+    Mixing currencies is only possible through a context,
+    the plain `+` and `-` operators stay within one currency.
+*/
+#[derive(Clone, Copy, Default)]
+pub struct Context<'a> {
+    rates: Option<&'a dyn ExchangeRateProvider>,
+    rounding: RoundingPolicy
+}
+
+impl<'a> Context<'a> {
+    pub fn new(rates: &'a dyn ExchangeRateProvider) -> Context<'a> {
+        Context { rates: Some(rates), rounding: RoundingPolicy::default() }
+    }
+
+    pub fn with_rounding(self, rounding: RoundingPolicy) -> Context<'a> {
+        Context { rounding, ..self }
+    }
+
+    pub(crate) fn rates(&self) -> Option<&'a dyn ExchangeRateProvider> {
+        self.rates
+    }
+
+    pub fn rounding(&self) -> RoundingPolicy {
+        self.rounding
+    }
+
+    pub fn round(&self, money: Money) -> Money {
+        money.round(self.rounding)
+    }
+
+    pub fn convert(&self, money: Money, currency: Currency) -> Result<Money, ExchangeError> {
+        if money.currency == currency {
+            return Ok(money);
+        }
+
+        let rates = self.rates.ok_or(ExchangeError::NoRate { from: money.currency, to: currency })?;
+        money.convert_with(currency, rates, self.rounding)
+    }
+
+    // The result is in the currency of `a`
+    pub fn add(&self, a: Money, b: Money) -> Result<Money, ExchangeError> {
+        Ok(a + self.convert(b, a.currency)?)
+    }
+
+    pub fn sub(&self, a: Money, b: Money) -> Result<Money, ExchangeError> {
+        Ok(a - self.convert(b, a.currency)?)
+    }
+
+    // 42€ + 12%
+    pub fn add_percentage(&self, money: Money, percentage: Percentage) -> Money {
+        self.round(Money::new(money.amount + money.percentage_part(percentage), money.currency))
+    }
+
+    // 42€ - 12%
+    pub fn sub_percentage(&self, money: Money, percentage: Percentage) -> Money {
+        self.round(Money::new(money.amount - money.percentage_part(percentage), money.currency))
+    }
+
+    // 12% of 42€
+    pub fn percentage_of(&self, money: Money, percentage: Percentage) -> Money {
+        self.round(Money::new(money.percentage_part(percentage), money.currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::RateTable;
+    use crate::units::rounding::RoundingMode;
+
+    #[test]
+    fn add_across_currencies() {
+        let rates = RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.05));
+        let ctx = Context::new(&rates);
+
+        let bob = Money::new(dec!(1337.0), Currency::USD);
+        let alice = Money::new(dec!(42.0), Currency::EUR);
+        assert_eq!(ctx.add(bob, alice), Ok(Money::new(dec!(1381.1), Currency::USD)));
+        assert_eq!(ctx.sub(alice, Money::new(dec!(21), Currency::USD)), Ok(Money::new(dec!(22), Currency::EUR)));
+    }
+
+    #[test]
+    fn missing_rate() {
+        let rates = RateTable::new();
+        let ctx = Context::new(&rates);
+
+        assert_eq!(
+            ctx.add(Money::new(dec!(1), Currency::EUR), Money::new(dec!(1), Currency::GBP)),
+            Err(ExchangeError::NoRate { from: Currency::GBP, to: Currency::EUR })
+        );
+        assert!(Context::default().convert(Money::new(dec!(1), Currency::EUR), Currency::GBP).is_err());
+    }
+
+    #[test]
+    fn rounding_policy() {
+        let price = Money::new(dec!(19.90), Currency::CHF);
+
+        let cash = Context::default().with_rounding(RoundingPolicy::cash(dec!(0.05)));
+        assert_eq!(cash.add_percentage(price, Percentage::new(8.1)), Money::new(dec!(21.50), Currency::CHF));
+
+        let floor = Context::default().with_rounding(RoundingPolicy::new(RoundingMode::Floor));
+        assert_eq!(floor.percentage_of(price, Percentage::new(8.1)), Money::new(dec!(1.61), Currency::CHF));
+        assert_eq!(floor.sub_percentage(price, Percentage::new(8.1)), Money::new(dec!(18.28), Currency::CHF));
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::custom::find_custom;
+
+// Currency Type
+//
+// A currency is a plain record of its ISO 4217 data, so it stays `Copy`
+// and can be compared, hashed and sorted (by code first).
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Currency {
+    pub code: &'static str,
+    pub numeric: u16,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub minor_units: u8,
+    pub symbol_first: bool
+}
+
+/*
+    ISO 4217 registry
+
+    One line per currency:
+    code numeric minor_units symbol symbol_first name
+
+    Every code becomes an associated constant (Currency::EUR)
+    and an entry of the ISO_4217 table.
+*/
+macro_rules! iso_4217 {
+    ($($code:ident $numeric:literal $minor_units:literal $symbol:literal $symbol_first:literal $name:literal;)*) => {
+        impl Currency {
+            $(
+                pub const $code: Currency = Currency {
+                    code: stringify!($code),
+                    numeric: $numeric,
+                    name: $name,
+                    symbol: $symbol,
+                    minor_units: $minor_units,
+                    symbol_first: $symbol_first
+                };
+            )*
+        }
+
+        // Active ISO 4217 currencies, funds and precious metals are left out.
+        pub static ISO_4217: &[Currency] = &[$(Currency::$code),*];
+    };
+}
+
+iso_4217! {
+    AED 784 2 "AED" false "UAE Dirham";
+    AFN 971 2 "AFN" false "Afghani";
+    ALL 8 2 "ALL" false "Lek";
+    AMD 51 2 "AMD" false "Armenian Dram";
+    ANG 532 2 "ANG" false "Netherlands Antillean Guilder";
+    AOA 973 2 "AOA" false "Kwanza";
+    ARS 32 2 "ARS" false "Argentine Peso";
+    AUD 36 2 "A$" true "Australian Dollar";
+    AWG 533 2 "AWG" false "Aruban Florin";
+    AZN 944 2 "AZN" false "Azerbaijan Manat";
+    BAM 977 2 "BAM" false "Convertible Mark";
+    BBD 52 2 "BBD" false "Barbados Dollar";
+    BDT 50 2 "৳" true "Taka";
+    BGN 975 2 "BGN" false "Bulgarian Lev";
+    BHD 48 3 "BHD" false "Bahraini Dinar";
+    BIF 108 0 "BIF" false "Burundi Franc";
+    BMD 60 2 "BMD" false "Bermudian Dollar";
+    BND 96 2 "BND" false "Brunei Dollar";
+    BOB 68 2 "BOB" false "Boliviano";
+    BRL 986 2 "R$" true "Brazilian Real";
+    BSD 44 2 "BSD" false "Bahamian Dollar";
+    BTN 64 2 "BTN" false "Ngultrum";
+    BWP 72 2 "BWP" false "Pula";
+    BYN 933 2 "BYN" false "Belarusian Ruble";
+    BZD 84 2 "BZD" false "Belize Dollar";
+    CAD 124 2 "CA$" true "Canadian Dollar";
+    CDF 976 2 "CDF" false "Congolese Franc";
+    CHF 756 2 "CHF" false "Swiss Franc";
+    CLF 990 4 "CLF" false "Unidad de Fomento";
+    CLP 152 0 "CLP" false "Chilean Peso";
+    CNY 156 2 "CN¥" true "Yuan Renminbi";
+    COP 170 2 "COP" false "Colombian Peso";
+    CRC 188 2 "₡" true "Costa Rican Colon";
+    CUP 192 2 "CUP" false "Cuban Peso";
+    CVE 132 2 "CVE" false "Cabo Verde Escudo";
+    CZK 203 2 "Kč" false "Czech Koruna";
+    DJF 262 0 "DJF" false "Djibouti Franc";
+    DKK 208 2 "DKK" false "Danish Krone";
+    DOP 214 2 "DOP" false "Dominican Peso";
+    DZD 12 2 "DZD" false "Algerian Dinar";
+    EGP 818 2 "EGP" false "Egyptian Pound";
+    ERN 232 2 "ERN" false "Nakfa";
+    ETB 230 2 "ETB" false "Ethiopian Birr";
+    EUR 978 2 "€" false "Euro";
+    FJD 242 2 "FJD" false "Fiji Dollar";
+    FKP 238 2 "FKP" false "Falkland Islands Pound";
+    GBP 826 2 "£" true "Pound Sterling";
+    GEL 981 2 "₾" false "Lari";
+    GHS 936 2 "GHS" false "Ghana Cedi";
+    GIP 292 2 "GIP" false "Gibraltar Pound";
+    GMD 270 2 "GMD" false "Dalasi";
+    GNF 324 0 "GNF" false "Guinean Franc";
+    GTQ 320 2 "GTQ" false "Quetzal";
+    GYD 328 2 "GYD" false "Guyana Dollar";
+    HKD 344 2 "HK$" true "Hong Kong Dollar";
+    HNL 340 2 "HNL" false "Lempira";
+    HTG 332 2 "HTG" false "Gourde";
+    HUF 348 2 "Ft" false "Forint";
+    IDR 360 2 "Rp" true "Rupiah";
+    ILS 376 2 "₪" true "New Israeli Sheqel";
+    INR 356 2 "₹" true "Indian Rupee";
+    IQD 368 3 "IQD" false "Iraqi Dinar";
+    IRR 364 2 "IRR" false "Iranian Rial";
+    ISK 352 0 "ISK" false "Iceland Krona";
+    JMD 388 2 "JMD" false "Jamaican Dollar";
+    JOD 400 3 "JOD" false "Jordanian Dinar";
+    JPY 392 0 "¥" true "Yen";
+    KES 404 2 "KES" false "Kenyan Shilling";
+    KGS 417 2 "KGS" false "Som";
+    KHR 116 2 "KHR" false "Riel";
+    KMF 174 0 "KMF" false "Comorian Franc";
+    KPW 408 2 "KPW" false "North Korean Won";
+    KRW 410 0 "₩" true "Won";
+    KWD 414 3 "KWD" false "Kuwaiti Dinar";
+    KYD 136 2 "KYD" false "Cayman Islands Dollar";
+    KZT 398 2 "₸" false "Tenge";
+    LAK 418 2 "LAK" false "Lao Kip";
+    LBP 422 2 "LBP" false "Lebanese Pound";
+    LKR 144 2 "LKR" false "Sri Lanka Rupee";
+    LRD 430 2 "LRD" false "Liberian Dollar";
+    LSL 426 2 "LSL" false "Loti";
+    LYD 434 3 "LYD" false "Libyan Dinar";
+    MAD 504 2 "MAD" false "Moroccan Dirham";
+    MDL 498 2 "MDL" false "Moldovan Leu";
+    MGA 969 2 "MGA" false "Malagasy Ariary";
+    MKD 807 2 "MKD" false "Denar";
+    MMK 104 2 "MMK" false "Kyat";
+    MNT 496 2 "₮" true "Tugrik";
+    MOP 446 2 "MOP" false "Pataca";
+    MRU 929 2 "MRU" false "Ouguiya";
+    MUR 480 2 "MUR" false "Mauritius Rupee";
+    MVR 462 2 "MVR" false "Rufiyaa";
+    MWK 454 2 "MWK" false "Malawi Kwacha";
+    MXN 484 2 "MX$" true "Mexican Peso";
+    MYR 458 2 "RM" true "Malaysian Ringgit";
+    MZN 943 2 "MZN" false "Mozambique Metical";
+    NAD 516 2 "NAD" false "Namibia Dollar";
+    NGN 566 2 "₦" true "Naira";
+    NIO 558 2 "NIO" false "Cordoba Oro";
+    NOK 578 2 "NOK" false "Norwegian Krone";
+    NPR 524 2 "NPR" false "Nepalese Rupee";
+    NZD 554 2 "NZ$" true "New Zealand Dollar";
+    OMR 512 3 "OMR" false "Rial Omani";
+    PAB 590 2 "PAB" false "Balboa";
+    PEN 604 2 "PEN" false "Sol";
+    PGK 598 2 "PGK" false "Kina";
+    PHP 608 2 "₱" true "Philippine Peso";
+    PKR 586 2 "PKR" false "Pakistan Rupee";
+    PLN 985 2 "zł" false "Zloty";
+    PYG 600 0 "₲" true "Guarani";
+    QAR 634 2 "QAR" false "Qatari Rial";
+    RON 946 2 "RON" false "Romanian Leu";
+    RSD 941 2 "RSD" false "Serbian Dinar";
+    RUB 643 2 "₽" false "Russian Ruble";
+    RWF 646 0 "RWF" false "Rwanda Franc";
+    SAR 682 2 "SAR" false "Saudi Riyal";
+    SBD 90 2 "SBD" false "Solomon Islands Dollar";
+    SCR 690 2 "SCR" false "Seychelles Rupee";
+    SDG 938 2 "SDG" false "Sudanese Pound";
+    SEK 752 2 "SEK" false "Swedish Krona";
+    SGD 702 2 "S$" true "Singapore Dollar";
+    SHP 654 2 "SHP" false "Saint Helena Pound";
+    SLE 925 2 "SLE" false "Leone";
+    SOS 706 2 "SOS" false "Somali Shilling";
+    SRD 968 2 "SRD" false "Surinam Dollar";
+    SSP 728 2 "SSP" false "South Sudanese Pound";
+    STN 930 2 "STN" false "Dobra";
+    SVC 222 2 "SVC" false "El Salvador Colon";
+    SYP 760 2 "SYP" false "Syrian Pound";
+    SZL 748 2 "SZL" false "Lilangeni";
+    THB 764 2 "฿" true "Baht";
+    TJS 972 2 "TJS" false "Somoni";
+    TMT 934 2 "TMT" false "Turkmenistan New Manat";
+    TND 788 3 "TND" false "Tunisian Dinar";
+    TOP 776 2 "TOP" false "Pa'anga";
+    TRY 949 2 "₺" true "Turkish Lira";
+    TTD 780 2 "TTD" false "Trinidad and Tobago Dollar";
+    TWD 901 2 "NT$" true "New Taiwan Dollar";
+    TZS 834 2 "TZS" false "Tanzanian Shilling";
+    UAH 980 2 "₴" false "Hryvnia";
+    UGX 800 0 "UGX" false "Uganda Shilling";
+    USD 840 2 "$" true "US Dollar";
+    UYU 858 2 "UYU" false "Peso Uruguayo";
+    UYW 927 4 "UYW" false "Unidad Previsional";
+    UZS 860 2 "UZS" false "Uzbekistan Sum";
+    VES 928 2 "VES" false "Bolivar Soberano";
+    VND 704 0 "₫" false "Dong";
+    VUV 548 0 "VUV" false "Vatu";
+    WST 882 2 "WST" false "Tala";
+    XAF 950 0 "FCFA" false "CFA Franc BEAC";
+    XCD 951 2 "EC$" true "East Caribbean Dollar";
+    XOF 952 0 "CFA" false "CFA Franc BCEAO";
+    XPF 953 0 "XPF" false "CFP Franc";
+    YER 886 2 "YER" false "Yemeni Rial";
+    ZAR 710 2 "R" true "Rand";
+    ZMW 967 2 "ZMW" false "Zambian Kwacha";
+    ZWG 924 2 "ZWG" false "Zimbabwe Gold";
+}
+
+impl Currency {
+    // ISO 4217 first, then the currencies registered at runtime
+    pub fn from_code(code: &str) -> Option<Currency> {
+        find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn from_numeric(numeric: u16) -> Option<Currency> {
+        ISO_4217.iter().find(|c| c.numeric == numeric).copied()
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Currency> {
+        find(|c| c.symbol == symbol)
+    }
+
+    pub fn from_name(name: &str) -> Option<Currency> {
+        find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    // Symbols made of letters (CHF, zł) need a space next to the amount
+    pub(crate) fn symbol_is_word(&self) -> bool {
+        let next_to_amount = if self.symbol_first {
+            self.symbol.chars().last()
+        } else {
+            self.symbol.chars().next()
+        };
+
+        next_to_amount.is_some_and(char::is_alphabetic)
+    }
+}
+
+fn find(predicate: impl Fn(&Currency) -> bool) -> Option<Currency> {
+    ISO_4217.iter().find(|c| predicate(c)).copied().or_else(|| find_custom(predicate))
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol)
+    }
+}
+
+// Parse Currency from Str
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseCurrencyError;
+
+impl FromStr for Currency {
+    type Err = ParseCurrencyError;
+
+    // Accepts an ISO code (EUR), a symbol (€) or a name (Euro)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        Currency::from_code(s)
+            .or_else(|| Currency::from_symbol(s))
+            .or_else(|| Currency::from_name(s))
+            .ok_or(ParseCurrencyError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn currency_from_str() {
+        assert_eq!(Currency::from_str("€"), Ok(Currency::EUR));
+        assert_eq!(Currency::from_str("$"), Ok(Currency::USD));
+        assert_eq!(Currency::from_str("Nothing to see here"), Err(ParseCurrencyError));
+    }
+
+    #[test]
+    fn from_code() {
+        assert_eq!(Currency::from_str("GBP"), Ok(Currency::GBP));
+        assert_eq!(Currency::from_str("chf"), Ok(Currency::CHF));
+        assert_eq!(Currency::from_numeric(392), Some(Currency::JPY));
+    }
+
+    #[test]
+    fn from_symbol() {
+        assert_eq!(Currency::from_str("£"), Ok(Currency::GBP));
+        assert_eq!(Currency::from_str("¥"), Ok(Currency::JPY));
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Currency::from_str("Swiss Franc"), Ok(Currency::CHF));
+        assert_eq!(Currency::from_str("euro"), Ok(Currency::EUR));
+    }
+
+    #[test]
+    fn registry_is_unique() {
+        for (i, a) in ISO_4217.iter().enumerate() {
+            for b in &ISO_4217[i + 1..] {
+                assert_ne!(a.code, b.code);
+                assert_ne!(a.numeric, b.numeric);
+                assert_ne!(a.symbol, b.symbol);
+            }
+        }
+    }
+}
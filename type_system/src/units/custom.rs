@@ -0,0 +1,264 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::currency::{Currency, ISO_4217};
+
+/*
+    Custom currencies
+
+    Internal credits, crypto tokens... anything that is not in ISO 4217
+    can be registered at runtime and is then found by `from_code`,
+    `from_symbol`, `FromStr` and everything built on them (Money parsing,
+    serde). Custom currencies have no numeric code (0).
+
+    Registered currencies live until the end of the program: their
+    strings are leaked so `Currency` stays `Copy`.
+*/
+static CUSTOM: RwLock<Vec<Currency>> = RwLock::new(Vec::new());
+
+#[derive(Debug)]
+pub enum CustomCurrencyError {
+    // codes are 2 to 10 ASCII letters, like ISO 4217 ones
+    InvalidCode(String),
+    // an ISO code, or a custom code registered with different data
+    AlreadyDefined(String),
+    // codes come first in `FromStr`: "RM" would hide the ringgit
+    CodeTaken(String),
+    // at most 28, the scale of a Decimal
+    InvalidMinorUnits(u8),
+    // symbols must find their currency back: "$" is USD
+    SymbolTaken(String),
+    Io(std::io::Error),
+    Csv { line: usize, message: String }
+}
+
+impl fmt::Display for CustomCurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomCurrencyError::InvalidCode(code) => write!(f, "invalid currency code {:?}", code),
+            CustomCurrencyError::AlreadyDefined(code) => write!(f, "currency {} is already defined", code),
+            CustomCurrencyError::CodeTaken(code) => write!(f, "code {:?} is the symbol or name of another currency", code),
+            CustomCurrencyError::InvalidMinorUnits(minor_units) => write!(f, "invalid minor units {}, at most 28", minor_units),
+            CustomCurrencyError::SymbolTaken(symbol) => write!(f, "symbol {:?} belongs to another currency", symbol),
+            CustomCurrencyError::Io(e) => write!(f, "can't read currencies: {}", e),
+            CustomCurrencyError::Csv { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for CustomCurrencyError {}
+
+impl From<std::io::Error> for CustomCurrencyError {
+    fn from(e: std::io::Error) -> Self {
+        CustomCurrencyError::Io(e)
+    }
+}
+
+impl Currency {
+    /*
+        Register a currency that is not in ISO 4217
+
+        Registering the same currency twice is fine,
+        redefining a code with other data is not, and neither is
+        a code or symbol that already finds another currency.
+    */
+    pub fn register(code: &str, symbol: &str, minor_units: u8, symbol_first: bool, name: &str) -> Result<Currency, CustomCurrencyError> {
+        let code = code.trim().to_ascii_uppercase();
+        if !(2..=10).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(CustomCurrencyError::InvalidCode(code));
+        }
+
+        if ISO_4217.iter().any(|c| c.code == code) {
+            return Err(CustomCurrencyError::AlreadyDefined(code));
+        }
+
+        if minor_units > 28 {
+            return Err(CustomCurrencyError::InvalidMinorUnits(minor_units));
+        }
+
+        let symbol = if symbol.trim().is_empty() { code.as_str() } else { symbol.trim() };
+        let name = if name.trim().is_empty() { code.as_str() } else { name.trim() };
+
+        let mut custom = CUSTOM.write().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(existing) = custom.iter().find(|c| c.code == code) {
+            let same = existing.symbol == symbol && existing.minor_units == minor_units
+                && existing.symbol_first == symbol_first && existing.name == name;
+
+            return if same { Ok(*existing) } else { Err(CustomCurrencyError::AlreadyDefined(code)) };
+        }
+
+        let others = || ISO_4217.iter().chain(custom.iter());
+
+        if others().any(|c| c.symbol.eq_ignore_ascii_case(&code) || c.name.eq_ignore_ascii_case(&code)) {
+            return Err(CustomCurrencyError::CodeTaken(code));
+        }
+
+        if others().any(|c| c.symbol == symbol || c.code.eq_ignore_ascii_case(symbol)) {
+            return Err(CustomCurrencyError::SymbolTaken(symbol.to_owned()));
+        }
+
+        let currency = Currency {
+            code: leak(&code),
+            numeric: 0,
+            name: leak(name),
+            symbol: leak(symbol),
+            minor_units,
+            symbol_first
+        };
+
+        custom.push(currency);
+        Ok(currency)
+    }
+
+    // Currencies added with `register`, in registration order
+    pub fn custom_currencies() -> Vec<Currency> {
+        CUSTOM.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn is_iso(&self) -> bool {
+        self.numeric != 0
+    }
+
+    /*
+        Register the currencies of a config file
+
+        code,symbol,minor_units,position,name
+        CRD,cr,0,after,Team Credit
+        BTC,₿,8,before,Bitcoin
+
+        position is where the symbol goes: before or after the amount.
+    */
+    pub fn register_csv(s: &str) -> Result<Vec<Currency>, CustomCurrencyError> {
+        let mut currencies = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("code")) {
+                continue;
+            }
+
+            let error = |message: String| CustomCurrencyError::Csv { line: i + 1, message };
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [code, symbol, minor_units, position, name] = fields[..] else {
+                return Err(error(format!("expected 5 fields, got {}", fields.len())));
+            };
+
+            let minor_units = minor_units.parse::<u8>().ok()
+                .ok_or_else(|| error(format!("invalid minor units {:?}", minor_units)))?;
+
+            let symbol_first = match position {
+                "before" => true,
+                "after" => false,
+                _ => return Err(error(format!("invalid position {:?}, expected before or after", position))),
+            };
+
+            let currency = Currency::register(code, symbol, minor_units, symbol_first, name)
+                .map_err(|e| error(e.to_string()))?;
+            currencies.push(currency);
+        }
+
+        Ok(currencies)
+    }
+
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> Result<Vec<Currency>, CustomCurrencyError> {
+        Currency::register_csv(&fs::read_to_string(path)?)
+    }
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_owned().into_boxed_str())
+}
+
+// Lookup in the custom currencies, used after the ISO table
+pub(crate) fn find_custom(predicate: impl Fn(&Currency) -> bool) -> Option<Currency> {
+    CUSTOM.read().unwrap_or_else(|e| e.into_inner()).iter().find(|c| predicate(c)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::RateTable;
+    use crate::units::money::Money;
+
+    #[test]
+    fn register() {
+        let credit = Currency::register("tcr", "tc", 0, false, "Test Credit").unwrap();
+        assert_eq!(credit.code, "TCR");
+        assert!(!credit.is_iso());
+        assert!(Currency::EUR.is_iso());
+
+        assert_eq!(Currency::from_code("TCR"), Some(credit));
+        assert_eq!(Currency::from_str("Test Credit"), Ok(credit));
+        assert!(Currency::custom_currencies().contains(&credit));
+
+        // same definition again is fine, a different one is not
+        assert_eq!(Currency::register("TCR", "tc", 0, false, "Test Credit").unwrap(), credit);
+        assert!(matches!(Currency::register("TCR", "tc", 2, false, "Test Credit"), Err(CustomCurrencyError::AlreadyDefined(_))));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Currency::register("EUR", "E", 2, false, ""), Err(CustomCurrencyError::AlreadyDefined(_))));
+        assert!(matches!(Currency::register("X", "x", 2, false, ""), Err(CustomCurrencyError::InvalidCode(_))));
+        assert!(matches!(Currency::register("NO-DASH", "x", 2, false, ""), Err(CustomCurrencyError::InvalidCode(_))));
+        assert!(matches!(Currency::register("AB1", "x", 2, false, ""), Err(CustomCurrencyError::InvalidCode(_))));
+        assert!(matches!(Currency::register("XBT", "x", 30, false, ""), Err(CustomCurrencyError::InvalidMinorUnits(30))));
+        assert!(matches!(Currency::register("TDL", "$", 2, true, "Test Dollar"), Err(CustomCurrencyError::SymbolTaken(_))));
+        assert_eq!(Currency::from_code("TDL"), None);
+        assert!(matches!(Currency::register("TDL", "GBP", 2, true, "Test Dollar"), Err(CustomCurrencyError::SymbolTaken(_))));
+
+        // the ringgit's symbol and the euro's name still find them
+        assert!(matches!(Currency::register("RM", "x", 2, false, ""), Err(CustomCurrencyError::CodeTaken(_))));
+        assert!(matches!(Currency::register("euro", "x", 2, false, ""), Err(CustomCurrencyError::CodeTaken(_))));
+        assert_eq!(Currency::from_str("RM"), Ok(Currency::MYR));
+        assert_eq!(Currency::from_str("EURO"), Ok(Currency::EUR));
+
+        Currency::register("TSA", "§", 2, false, "Test A").unwrap();
+        assert!(matches!(Currency::register("TSB", "§", 2, false, "Test B"), Err(CustomCurrencyError::SymbolTaken(_))));
+    }
+
+    #[test]
+    fn money() {
+        let token = Currency::register("TTK", "⧫", 4, true, "Test Token").unwrap();
+
+        let money = Money::from_str("⧫1.5").unwrap();
+        assert_eq!(money, Money::new(dec!(1.5), token));
+        assert_eq!(money.to_string(), "⧫1.5000");
+        assert_eq!(Money::from_str("12 TTK"), Ok(Money::new(dec!(12), token)));
+
+        let rates = RateTable::new().with_rate(token, Currency::EUR, dec!(2));
+        assert_eq!(money.convert(Currency::EUR, &rates), Ok(Money::new(dec!(3), Currency::EUR)));
+    }
+
+    #[test]
+    fn config_file() {
+        let currencies = Currency::load_csv(format!("{}/fixtures/currencies.csv", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(currencies.len(), 4);
+
+        let btc = Currency::from_code("btc").unwrap();
+        assert_eq!(btc.minor_units, 8);
+        assert_eq!(Money::new(dec!(0.5), btc).to_string(), "₿0.50000000");
+
+        let credits = Money::from_str("120 cr").unwrap();
+        assert_eq!(credits.currency.code, "CRD");
+        assert_eq!(credits.to_string(), "120 cr");
+    }
+
+    #[test]
+    fn config_errors() {
+        let error = Currency::register_csv("code,symbol,minor_units,position,name\nQQA,q,2,left,Q").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: invalid position \"left\", expected before or after");
+
+        let error = Currency::register_csv("USD,$,2,before,Dollar").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: currency USD is already defined");
+
+        let error = Currency::register_csv("QQB,q,29,after,Q").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: invalid minor units 29, at most 28");
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+
+use super::exchange::ExchangeError;
+
+// Everything that can go wrong in the checked_* arithmetic of the units
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum UnitError {
+    DivisionByZero,
+    Overflow,
+    // i.e 12€ + $3, 12% - 4
+    IncompatibleUnits(String),
+    UnknownConversion(ExchangeError),
+    NaN,
+    // mean, median... of nothing
+    Empty
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitError::DivisionByZero => write!(f, "division by zero"),
+            UnitError::Overflow => write!(f, "overflow"),
+            UnitError::IncompatibleUnits(operation) => write!(f, "incompatible units: {}", operation),
+            UnitError::UnknownConversion(e) => write!(f, "{}", e),
+            UnitError::NaN => write!(f, "not a number"),
+            UnitError::Empty => write!(f, "no values"),
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+impl From<ExchangeError> for UnitError {
+    fn from(e: ExchangeError) -> Self {
+        UnitError::UnknownConversion(e)
+    }
+}
+
+// f64 results must stay finite
+pub(crate) fn finite(value: f64) -> Result<f64, UnitError> {
+    if value.is_nan() {
+        Err(UnitError::NaN)
+    } else if value.is_infinite() {
+        Err(UnitError::Overflow)
+    } else {
+        Ok(value)
+    }
+}
+
+pub(crate) fn checked_decimal(value: f64) -> Result<Decimal, UnitError> {
+    Decimal::from_f64(finite(value)?).ok_or(UnitError::Overflow)
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::fmt;
+use rust_decimal::Decimal;
+
+use super::currency::Currency;
+
+// Something that knows how much one unit of `from` is worth in `to`
+pub trait ExchangeRateProvider {
+    fn rate(&self, from: Currency, to: Currency) -> Result<Decimal, ExchangeError>;
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ExchangeError {
+    NoRate { from: Currency, to: Currency }
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::NoRate { from, to } => {
+                write!(f, "no exchange rate from {} to {}", from.code, to.code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExchangeError {}
+
+
+/*
+    In memory rate table
+
+    Only one direction of a pair needs to be known, the other one is
+    the inverse. Pairs that are not quoted at all go through the base
+    currency: CHF -> JPY = CHF -> EUR -> JPY
+*/
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    base: Option<Currency>,
+    rates: HashMap<(Currency, Currency), Decimal>
+}
+
+impl RateTable {
+    pub fn new() -> RateTable {
+        RateTable::default()
+    }
+
+    pub fn with_base(base: Currency) -> RateTable {
+        RateTable { base: Some(base), rates: HashMap::new() }
+    }
+
+    pub fn base(&self) -> Option<Currency> {
+        self.base
+    }
+
+    // 1 `from` = `rate` `to`
+    pub fn insert(&mut self, from: Currency, to: Currency, rate: Decimal) {
+        self.rates.insert((from, to), rate);
+    }
+
+    pub fn with_rate(mut self, from: Currency, to: Currency, rate: Decimal) -> RateTable {
+        self.insert(from, to, rate);
+        self
+    }
+
+    fn direct(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        if let Some(rate) = self.rates.get(&(from, to)) {
+            return Some(*rate);
+        }
+
+        self.rates.get(&(to, from))
+            .filter(|rate| !rate.is_zero())
+            .map(|rate| Decimal::ONE / rate)
+    }
+}
+
+impl ExchangeRateProvider for RateTable {
+    fn rate(&self, from: Currency, to: Currency) -> Result<Decimal, ExchangeError> {
+        if let Some(rate) = self.direct(from, to) {
+            return Ok(rate);
+        }
+
+        // triangulate through the base currency
+        self.base
+            .and_then(|base| Some(self.direct(from, base)? * self.direct(base, to)?))
+            .ok_or(ExchangeError::NoRate { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn table() -> RateTable {
+        RateTable::with_base(Currency::EUR)
+            .with_rate(Currency::EUR, Currency::USD, dec!(1.05))
+            .with_rate(Currency::EUR, Currency::CHF, dec!(0.95))
+            .with_rate(Currency::JPY, Currency::EUR, dec!(0.0064))
+    }
+
+    #[test]
+    fn direct_rate() {
+        assert_eq!(table().rate(Currency::EUR, Currency::USD), Ok(dec!(1.05)));
+        assert_eq!(table().rate(Currency::USD, Currency::USD), Ok(dec!(1)));
+    }
+
+    #[test]
+    fn inverse_rate() {
+        assert_eq!(table().rate(Currency::EUR, Currency::JPY), Ok(dec!(156.25)));
+    }
+
+    #[test]
+    fn triangulation() {
+        let chf_usd = table().rate(Currency::CHF, Currency::USD).unwrap();
+        assert_eq!(chf_usd.round_dp(6), dec!(1.105263));
+        assert_eq!(table().rate(Currency::JPY, Currency::USD), Ok(dec!(0.00672)));
+    }
+
+    #[test]
+    fn no_path() {
+        let rates = RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.05));
+        assert_eq!(
+            rates.rate(Currency::GBP, Currency::USD),
+            Err(ExchangeError::NoRate { from: Currency::GBP, to: Currency::USD })
+        );
+        assert_eq!(
+            ExchangeError::NoRate { from: Currency::GBP, to: Currency::USD }.to_string(),
+            "no exchange rate from GBP to USD"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use super::money::Money;
+use super::rounding::RoundingPolicy;
+use crate::Percentage;
+
+/*
+    Locale profiles
+
+    de_DE: 1.234,50 €
+    en_US: $1,234.50
+    fr_CH: CHF 1'234.50
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Locale {
+    pub name: &'static str,
+    pub decimal_separator: char,
+    pub grouping_separator: char,
+    pub symbol_first: bool,
+    pub symbol_space: bool,
+    pub percent_space: bool
+}
+
+impl Locale {
+    pub const DE_DE: Locale = Locale {
+        name: "de_DE",
+        decimal_separator: ',',
+        grouping_separator: '.',
+        symbol_first: false,
+        symbol_space: true,
+        percent_space: true
+    };
+
+    pub const EN_US: Locale = Locale {
+        name: "en_US",
+        decimal_separator: '.',
+        grouping_separator: ',',
+        symbol_first: true,
+        symbol_space: false,
+        percent_space: false
+    };
+
+    pub const FR_CH: Locale = Locale {
+        name: "fr_CH",
+        decimal_separator: '.',
+        grouping_separator: '\'',
+        symbol_first: true,
+        symbol_space: true,
+        percent_space: true
+    };
+
+    pub const ALL: [Locale; 3] = [Locale::DE_DE, Locale::EN_US, Locale::FR_CH];
+
+    pub fn from_name(name: &str) -> Option<Locale> {
+        Locale::ALL.into_iter().find(|locale| locale.name == name)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum NegativeStyle {
+    // -$5.00
+    #[default]
+    Minus,
+    // ($5.00), accounting style
+    Parentheses
+}
+
+/*
+    Locale aware formatting of Money and Percentage,
+    the Display implementations stay locale free.
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct LocaleFormatter {
+    pub locale: Locale,
+    pub negative_style: NegativeStyle,
+    pub percent_precision: usize,
+    pub rounding: RoundingPolicy
+}
+
+impl LocaleFormatter {
+    pub fn new(locale: Locale) -> LocaleFormatter {
+        LocaleFormatter {
+            locale,
+            negative_style: NegativeStyle::default(),
+            percent_precision: 1,
+            rounding: RoundingPolicy::default()
+        }
+    }
+
+    pub fn with_negative_style(self, negative_style: NegativeStyle) -> LocaleFormatter {
+        LocaleFormatter { negative_style, ..self }
+    }
+
+    pub fn with_percent_precision(self, percent_precision: usize) -> LocaleFormatter {
+        LocaleFormatter { percent_precision, ..self }
+    }
+
+    pub fn with_rounding(self, rounding: RoundingPolicy) -> LocaleFormatter {
+        LocaleFormatter { rounding, ..self }
+    }
+
+    pub fn money(&self, money: Money) -> String {
+        let money = money.round(self.rounding);
+        let digits = format!("{:.*}", usize::from(money.currency.minor_units), money.amount.abs());
+        let number = self.localize(&digits);
+
+        let space = if self.locale.symbol_space { " " } else { "" };
+        let body = if self.locale.symbol_first {
+            format!("{}{}{}", money.currency.symbol, space, number)
+        } else {
+            format!("{}{}{}", number, space, money.currency.symbol)
+        };
+
+        self.sign(body, money.amount.is_sign_negative() && !money.amount.is_zero())
+    }
+
+    pub fn percentage(&self, percentage: Percentage) -> String {
+        let digits = format!("{:.*}", self.percent_precision, percentage.value.abs());
+        let space = if self.locale.percent_space { " " } else { "" };
+        let body = format!("{}{}%", self.localize(&digits), space);
+
+        // -0.0 must not print as -0%
+        let negative = percentage.value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0');
+        self.sign(body, negative)
+    }
+
+    fn sign(&self, body: String, negative: bool) -> String {
+        match (negative, self.negative_style) {
+            (false, _) => body,
+            (true, NegativeStyle::Minus) => format!("-{}", body),
+            (true, NegativeStyle::Parentheses) => format!("({})", body),
+        }
+    }
+
+    // "1234.50" -> "1.234,50"
+    fn localize(&self, digits: &str) -> String {
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let mut grouped = String::new();
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(self.locale.grouping_separator);
+            }
+            grouped.push(c);
+        }
+
+        if !fraction.is_empty() {
+            grouped.push(self.locale.decimal_separator);
+            grouped.push_str(fraction);
+        }
+
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::currency::Currency;
+
+    #[test]
+    fn locales() {
+        assert_eq!(LocaleFormatter::new(Locale::DE_DE).money(Money::new(dec!(1234.5), Currency::EUR)), "1.234,50 €");
+        assert_eq!(LocaleFormatter::new(Locale::EN_US).money(Money::new(dec!(1234.5), Currency::USD)), "$1,234.50");
+        assert_eq!(LocaleFormatter::new(Locale::FR_CH).money(Money::new(dec!(1234.5), Currency::CHF)), "CHF 1'234.50");
+        assert_eq!(Locale::from_name("fr_CH"), Some(Locale::FR_CH));
+    }
+
+    #[test]
+    fn grouping() {
+        let us = LocaleFormatter::new(Locale::EN_US);
+        assert_eq!(us.money(Money::new(dec!(123), Currency::USD)), "$123.00");
+        assert_eq!(us.money(Money::new(dec!(1234567.891), Currency::USD)), "$1,234,567.89");
+        assert_eq!(us.money(Money::new(dec!(1234567), Currency::JPY)), "¥1,234,567");
+    }
+
+    #[test]
+    fn negative_amounts() {
+        let us = LocaleFormatter::new(Locale::EN_US);
+        assert_eq!(us.money(Money::new(dec!(-5), Currency::USD)), "-$5.00");
+
+        let accounting = us.with_negative_style(NegativeStyle::Parentheses);
+        assert_eq!(accounting.money(Money::new(dec!(-5), Currency::USD)), "($5.00)");
+        assert_eq!(accounting.money(Money::new(dec!(-0.001), Currency::USD)), "$0.00");
+
+        let de = LocaleFormatter::new(Locale::DE_DE);
+        assert_eq!(de.money(Money::new(dec!(-1234.5), Currency::EUR)), "-1.234,50 €");
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(LocaleFormatter::new(Locale::EN_US).percentage(Percentage::new(12.5)), "12.5%");
+        assert_eq!(LocaleFormatter::new(Locale::DE_DE).percentage(Percentage::new(12.5)), "12,5 %");
+
+        let precise = LocaleFormatter::new(Locale::DE_DE).with_percent_precision(3);
+        assert_eq!(precise.percentage(Percentage::new(-1234.5)), "-1.234,500 %");
+        assert_eq!(precise.with_percent_precision(0).percentage(Percentage::new(-0.2)), "0 %");
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+
+use super::currency::Currency;
+use super::exchange::RateTable;
+
+/*
+    Historical exchange rates
+
+    A time series of rates per currency pair. `on(date)` gives the
+    rates valid on that date as a `RateTable`, so conversions at a
+    past date work exactly like conversions with today's rates.
+
+    Days without a quote (week-ends, bank holidays) fall back to the
+    last previous day that has one, at most `max_lookback` days back.
+    An older rate is not used: the pair has no rate on that date.
+*/
+#[derive(Debug, Clone)]
+pub struct HistoricalRates {
+    base: Option<Currency>,
+    max_lookback: u64,
+    series: HashMap<(Currency, Currency), BTreeMap<NaiveDate, Decimal>>
+}
+
+// Long enough for Easter, from Thursday to the Monday after
+pub const DEFAULT_MAX_LOOKBACK: u64 = 5;
+
+#[derive(Debug)]
+pub enum LoadRatesError {
+    Io(std::io::Error),
+    Csv { line: usize, message: String },
+    Xml(String)
+}
+
+impl fmt::Display for LoadRatesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadRatesError::Io(e) => write!(f, "can't read rates: {}", e),
+            LoadRatesError::Csv { line, message } => write!(f, "line {}: {}", line, message),
+            LoadRatesError::Xml(message) => write!(f, "invalid ECB rates: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LoadRatesError {}
+
+impl From<std::io::Error> for LoadRatesError {
+    fn from(e: std::io::Error) -> Self {
+        LoadRatesError::Io(e)
+    }
+}
+
+impl Default for HistoricalRates {
+    fn default() -> Self {
+        HistoricalRates { base: None, max_lookback: DEFAULT_MAX_LOOKBACK, series: HashMap::new() }
+    }
+}
+
+impl HistoricalRates {
+    pub fn new() -> HistoricalRates {
+        HistoricalRates::default()
+    }
+
+    pub fn with_base(base: Currency) -> HistoricalRates {
+        HistoricalRates { base: Some(base), ..HistoricalRates::default() }
+    }
+
+    // How many days before a date its last quote can be
+    pub fn with_max_lookback(self, days: u64) -> HistoricalRates {
+        HistoricalRates { max_lookback: days, ..self }
+    }
+
+    // 1 `from` = `rate` `to` on `date`
+    pub fn insert(&mut self, date: NaiveDate, from: Currency, to: Currency, rate: Decimal) {
+        self.series.entry((from, to)).or_default().insert(date, rate);
+    }
+
+    // Rates valid on `date`
+    pub fn on(&self, date: NaiveDate) -> RateTable {
+        let mut table = match self.base {
+            Some(base) => RateTable::with_base(base),
+            None => RateTable::new(),
+        };
+
+        let oldest = date.checked_sub_days(Days::new(self.max_lookback)).unwrap_or(NaiveDate::MIN);
+
+        for (&(from, to), series) in &self.series {
+            if let Some((_, rate)) = series.range(oldest..=date).next_back() {
+                table.insert(from, to, *rate);
+            }
+        }
+
+        table
+    }
+
+    /*
+        CSV with one rate per line:
+
+        date,from,to,rate
+        2024-01-05,EUR,USD,1.0921
+    */
+    pub fn from_csv(s: &str) -> Result<HistoricalRates, LoadRatesError> {
+        let mut rates = HistoricalRates::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.starts_with("date")) {
+                continue;
+            }
+
+            let error = |message: String| LoadRatesError::Csv { line: i + 1, message };
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [date, from, to, rate] = fields[..] else {
+                return Err(error(format!("expected 4 fields, got {}", fields.len())));
+            };
+
+            let date = NaiveDate::from_str(date).map_err(|_| error(format!("invalid date {:?}", date)))?;
+            let from = Currency::from_code(from).ok_or_else(|| error(format!("unknown currency {:?}", from)))?;
+            let to = Currency::from_code(to).ok_or_else(|| error(format!("unknown currency {:?}", to)))?;
+            let rate = Decimal::from_str(rate).map_err(|_| error(format!("invalid rate {:?}", rate)))?;
+
+            rates.insert(date, from, to, rate);
+        }
+
+        Ok(rates)
+    }
+
+    /*
+        ECB reference rates (eurofxref-hist.xml)
+
+        <Cube time="2024-01-05">
+            <Cube currency="USD" rate="1.0921"/>
+        </Cube>
+
+        All rates are quoted against the euro, which becomes the base
+        currency. Currencies that no longer exist (CYP, SIT...) are skipped.
+    */
+    pub fn from_ecb_xml(s: &str) -> Result<HistoricalRates, LoadRatesError> {
+        let document = roxmltree::Document::parse(s).map_err(|e| LoadRatesError::Xml(e.to_string()))?;
+        let mut rates = HistoricalRates::with_base(Currency::EUR);
+
+        let days = document.descendants()
+            .filter(|node| node.has_tag_name("Cube"))
+            .filter_map(|node| Some((node, node.attribute("time")?)));
+
+        for (day, time) in days {
+            let date = NaiveDate::from_str(time)
+                .map_err(|_| LoadRatesError::Xml(format!("invalid date {:?}", time)))?;
+
+            for cube in day.children().filter(|node| node.has_tag_name("Cube")) {
+                let (Some(code), Some(rate)) = (cube.attribute("currency"), cube.attribute("rate")) else {
+                    continue;
+                };
+
+                let Some(currency) = Currency::from_code(code) else {
+                    continue;
+                };
+
+                let rate = Decimal::from_str(rate)
+                    .map_err(|_| LoadRatesError::Xml(format!("invalid rate {:?} for {} on {}", rate, code, date)))?;
+
+                rates.insert(date, Currency::EUR, currency, rate);
+            }
+        }
+
+        Ok(rates)
+    }
+
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> Result<HistoricalRates, LoadRatesError> {
+        HistoricalRates::from_csv(&fs::read_to_string(path)?)
+    }
+
+    pub fn load_ecb_xml<P: AsRef<Path>>(path: P) -> Result<HistoricalRates, LoadRatesError> {
+        HistoricalRates::from_ecb_xml(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::{ExchangeError, ExchangeRateProvider};
+    use crate::units::money::Money;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn ecb_xml() {
+        let rates = HistoricalRates::load_ecb_xml(fixture("eurofxref-hist.xml")).unwrap();
+
+        assert_eq!(rates.on(date("2024-01-04")).rate(Currency::EUR, Currency::USD), Ok(dec!(1.0953)));
+        assert_eq!(rates.on(date("2024-01-03")).rate(Currency::EUR, Currency::JPY), Ok(dec!(155.52)));
+    }
+
+    #[test]
+    fn previous_business_day() {
+        let rates = HistoricalRates::load_ecb_xml(fixture("eurofxref-hist.xml")).unwrap();
+
+        // 2024-01-07 is a Sunday, Friday's rate applies
+        let sunday = rates.on(date("2024-01-07"));
+        assert_eq!(sunday.rate(Currency::EUR, Currency::CHF), Ok(dec!(0.9293)));
+
+        let before = rates.on(date("2024-01-02"));
+        assert_eq!(before.rate(Currency::EUR, Currency::USD), Err(ExchangeError::NoRate { from: Currency::EUR, to: Currency::USD }));
+    }
+
+    #[test]
+    fn max_lookback() {
+        let mut rates = HistoricalRates::new();
+        rates.insert(date("2024-01-05"), Currency::EUR, Currency::USD, dec!(1.0921));
+        let no_rate = Err(ExchangeError::NoRate { from: Currency::EUR, to: Currency::USD });
+
+        assert_eq!(rates.on(date("2024-01-10")).rate(Currency::EUR, Currency::USD), Ok(dec!(1.0921)));
+        assert_eq!(rates.on(date("2024-01-11")).rate(Currency::EUR, Currency::USD), no_rate);
+        assert_eq!(rates.on(date("2025-01-05")).rate(Currency::EUR, Currency::USD), no_rate);
+
+        let rates = rates.with_max_lookback(0);
+        assert_eq!(rates.on(date("2024-01-05")).rate(Currency::EUR, Currency::USD), Ok(dec!(1.0921)));
+        assert_eq!(rates.on(date("2024-01-06")).rate(Currency::EUR, Currency::USD), no_rate);
+    }
+
+    #[test]
+    fn convert_on_date() {
+        let rates = HistoricalRates::load_ecb_xml(fixture("eurofxref-hist.xml")).unwrap();
+        let invoice = Money::new(dec!(1000), Currency::USD);
+
+        // triangulated through the euro
+        let gbp = invoice.convert(Currency::GBP, &rates.on(date("2024-01-05"))).unwrap();
+        assert_eq!(gbp.amount.round_dp(2), dec!(788.19));
+    }
+
+    #[test]
+    fn csv() {
+        let rates = HistoricalRates::load_csv(fixture("rates.csv")).unwrap();
+
+        assert_eq!(rates.on(date("2024-01-04")).rate(Currency::EUR, Currency::USD), Ok(dec!(1.0953)));
+        assert_eq!(rates.on(date("2024-01-06")).rate(Currency::GBP, Currency::USD), Ok(dec!(1.2703)));
+        assert!(rates.on(date("2024-01-04")).rate(Currency::GBP, Currency::USD).is_err());
+    }
+
+    #[test]
+    fn csv_errors() {
+        let error = HistoricalRates::from_csv("date,from,to,rate\n2024-01-05,EUR,XYZ,1.2").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown currency \"XYZ\"");
+
+        let error = HistoricalRates::from_csv("2024-01-05,EUR,USD").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: expected 4 fields, got 3");
+    }
+}
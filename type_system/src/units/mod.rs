@@ -1,3 +1,23 @@
+pub mod error;
+pub mod currency;
+pub mod custom;
+pub mod exchange;
+pub mod history;
+pub mod rounding;
+pub mod context;
 pub mod percentage;
+pub mod ratio;
 pub mod money;
-pub mod restype;
\ No newline at end of file
+pub mod moneybag;
+pub mod allocation;
+pub mod aggregate;
+pub mod compare;
+pub mod change;
+pub mod format;
+pub mod parse;
+pub mod restype;
+
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(test)]
+pub(crate) mod testing;
@@ -2,114 +2,111 @@ use std::fmt;
 use std::ops::{Add, Sub, Mul, Div};
 use std::str::FromStr;
 use duplicate::duplicate_item;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 
 use crate::Percentage;
-
-// Currency Type
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
-pub enum Currency {
-    Euros,
-    Dollars
-}
-
-impl fmt::Display for Currency {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let symbol = match self {
-            Currency::Euros => '€',
-            Currency::Dollars => '$',
-        };
-        write!(f, "{}", symbol)
-    }
-}
-
-// Parse Currency from Str
-#[derive(Debug, Eq, PartialEq)]
-pub struct ParseCurrencyError;
-
-impl FromStr for Currency {
-    type Err = ParseCurrencyError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let currency = match s {
-            "€" => Currency::Euros,
-            "$" => Currency::Dollars,
-            _ => return Err(ParseCurrencyError)
-        };
-
-        Ok(currency)
-    }
-}
-
+use super::currency::Currency;
+use super::exchange::{ExchangeError, ExchangeRateProvider};
+use super::rounding::RoundingPolicy;
+use super::parse::MoneyParser;
+use super::error::{UnitError, checked_decimal};
+pub use super::parse::ParseMoneyError;
 
 // Money Type
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Money {
-    pub amount: f64,
+    pub amount: Decimal,
     pub currency: Currency
 }
 
 impl Money {
-    pub fn new(amount: f64, currency: Currency) -> Money {
-        Money {amount: amount, currency: currency}
+    pub fn new(amount: Decimal, currency: Currency) -> Money {
+        Money {amount, currency}
     }
 
-    fn conversion(self, new_currency: Currency) -> Money {
-        if self.currency == new_currency {
-            return self;
-        }
-
-        if self.currency == Currency::Euros && new_currency == Currency::Dollars {
-            Money::new(self.amount * 1.05, new_currency)
-            
-        } else if self.currency == Currency::Dollars && new_currency == Currency::Euros {
-            Money::new(self.amount / 1.05, new_currency)
-            
-        } else {
-            unreachable!("Fuck, we need to thing about conversion now :(")
-        }
+    pub fn round(self, policy: RoundingPolicy) -> Money {
+        Money::new(policy.round(self.amount, self.currency.minor_units), self.currency)
     }
-}
 
-// Parse Currency from Str
-#[derive(Debug, Eq, PartialEq)]
-pub struct ParseMoneyError;
+    // Converted amounts are rounded with the default policy
+    pub fn convert(self, new_currency: Currency, rates: &dyn ExchangeRateProvider) -> Result<Money, ExchangeError> {
+        self.convert_with(new_currency, rates, RoundingPolicy::default())
+    }
 
-impl FromStr for Money {
-    type Err = ParseMoneyError;
+    pub fn convert_with(self, new_currency: Currency, rates: &dyn ExchangeRateProvider, policy: RoundingPolicy) -> Result<Money, ExchangeError> {
+        if self.currency == new_currency {
+            return Ok(self);
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rate = rates.rate(self.currency, new_currency)?;
+        Ok(Money::new(self.amount * rate, new_currency).round(policy))
+    }
 
-        let first_char = s.chars().next().ok_or(ParseMoneyError)?;
-        let last_char = s.chars().last().ok_or(ParseMoneyError)?;
+    // `percentage` of this amount, not rounded yet
+    pub(crate) fn percentage_part(self, percentage: Percentage) -> Decimal {
+        self.amount * percentage_rate(percentage)
+    }
 
-        let mut s: String = s.to_owned();
+    pub(crate) fn checked_percentage_part(self, percentage: Percentage) -> Result<Decimal, UnitError> {
+        let rate = checked_decimal(percentage.value)? / Decimal::ONE_HUNDRED;
+        self.amount.checked_mul(rate).ok_or(UnitError::Overflow)
+    }
 
-        // Currency symbol is at the start: $47.0
-        if !first_char.is_ascii_digit() {
-            let currency = Currency::from_str(&first_char.to_string()).map_err(|_| ParseMoneyError)?;
+    /*
+        Checked arithmetic
 
-            s.remove(0); // remove currency symbol / first char
-            let s = s.trim(); // remove whitespace
+        Same operations as the operators, but errors are returned
+        instead of panicking: 12€ + $3, 12€ / 0, overflows.
+    */
+    pub fn checked_add(self, rhs: Money) -> Result<Money, UnitError> {
+        self.same_currency(rhs, "+")?;
+        let amount = self.amount.checked_add(rhs.amount).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
 
-            let amount: f64 = f64::from_str(s).map_err(|_| ParseMoneyError)?;
+    pub fn checked_sub(self, rhs: Money) -> Result<Money, UnitError> {
+        self.same_currency(rhs, "-")?;
+        let amount = self.amount.checked_sub(rhs.amount).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
 
-            Ok(Money::new(amount, currency))
+    pub fn checked_mul(self, rhs: Decimal) -> Result<Money, UnitError> {
+        let amount = self.amount.checked_mul(rhs).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
 
+    pub fn checked_div(self, rhs: Decimal) -> Result<Money, UnitError> {
+        if rhs.is_zero() {
+            return Err(UnitError::DivisionByZero);
+        }
+        let amount = self.amount.checked_div(rhs).ok_or(UnitError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
 
-        // Currency symbol is at the end: 12.0€
-        } else if !last_char.is_ascii_digit() {
-            let currency = Currency::from_str(&last_char.to_string()).map_err(|_| ParseMoneyError)?;
+    // 100€ / 2€ = 50
+    pub fn checked_div_money(self, rhs: Money) -> Result<Decimal, UnitError> {
+        self.same_currency(rhs, "/")?;
+        if rhs.amount.is_zero() {
+            return Err(UnitError::DivisionByZero);
+        }
+        self.amount.checked_div(rhs.amount).ok_or(UnitError::Overflow)
+    }
 
-            s.pop(); // remove currency symbol / last char
-            let s: &str = s.trim(); // remove whitespace
-            
-            let amount: f64 = f64::from_str(s).map_err(|_| ParseMoneyError)?;
+    fn same_currency(self, rhs: Money, op: &str) -> Result<(), UnitError> {
+        if self.currency != rhs.currency {
+            return Err(UnitError::IncompatibleUnits(format!("{} {} {}", self, op, rhs)));
+        }
+        Ok(())
+    }
+}
 
-            Ok(Money::new(amount, currency))
+// Parse Money from Str, see `MoneyParser` for the accepted formats
+impl FromStr for Money {
+    type Err = ParseMoneyError;
 
-        } else {
-            Err(ParseMoneyError)
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MoneyParser::new().parse(s)
     }
 }
 
@@ -121,47 +118,56 @@ impl FromStr for Money {
     i.e 42€ + 10.0
 */
 
+// f64 goes through its shortest representation, so 1.05 stays exactly 1.05
+fn decimal_from_f64(value: f64) -> Decimal {
+    Decimal::from_f64(value).expect("Money can only be combined with finite numbers")
+}
+
+fn percentage_rate(percentage: Percentage) -> Decimal {
+    decimal_from_f64(percentage.value) / Decimal::ONE_HUNDRED
+}
+
 // implement Add for f64, i32
-#[duplicate_item(Type; [f64]; [i32];)]
+#[duplicate_item(Type to_decimal; [f64] [decimal_from_f64]; [i32] [Decimal::from];)]
 
 impl Add<Type> for Money {
     type Output = Money;
 
     fn add(self, rhs: Type) -> Self::Output {
-        Money::new(self.amount + f64::from(rhs), self.currency)
+        Money::new(self.amount + to_decimal(rhs), self.currency)
     }
 }
 
 // implement Sub for f64, i32
-#[duplicate_item(Type; [f64]; [i32];)]
+#[duplicate_item(Type to_decimal; [f64] [decimal_from_f64]; [i32] [Decimal::from];)]
 
 impl Sub<Type> for Money {
     type Output = Money;
 
     fn sub(self, rhs: Type) -> Self::Output {
-        Money::new(self.amount - f64::from(rhs), self.currency)
+        Money::new(self.amount - to_decimal(rhs), self.currency)
     }
 }
 
 // implement Mul for f64, i32
-#[duplicate_item(Type; [f64]; [i32];)]
+#[duplicate_item(Type to_decimal; [f64] [decimal_from_f64]; [i32] [Decimal::from];)]
 
 impl Mul<Type> for Money {
     type Output = Money;
 
     fn mul(self, rhs: Type) -> Self::Output {
-        Money::new(self.amount * f64::from(rhs), self.currency)
+        Money::new(self.amount * to_decimal(rhs), self.currency)
     }
 }
 
 // implement Div for f64, i32
-#[duplicate_item(Type; [f64]; [i32];)]
+#[duplicate_item(Type to_decimal; [f64] [decimal_from_f64]; [i32] [Decimal::from];)]
 
 impl Div<Type> for Money {
     type Output = Money;
 
     fn div(self, rhs: Type) -> Self::Output {
-        Money::new(self.amount / f64::from(rhs), self.currency)
+        Money::new(self.amount / to_decimal(rhs), self.currency)
     }
 }
 
@@ -169,6 +175,9 @@ impl Div<Type> for Money {
 /*
     Implement operation for Money with money
 
+    Both sides must be in the same currency, mixing currencies
+    needs exchange rates and goes through `Context`.
+
 impl Add<Money> for Money {
     type Output = Money;
 
@@ -185,7 +194,7 @@ macro_rules! impl_arithmetic_op_for_Money {
             type Output = Money;
         
             fn $fn_name(self, other: Money) -> Self::Output {
-                let other = other.conversion(self.currency);
+                assert_eq!(self.currency, other.currency, "{} {} {}: use a Context to mix currencies", self, stringify!($op), other);
                 Money::new(self.amount $op other.amount, self.currency)
             }
         }        
@@ -194,19 +203,27 @@ macro_rules! impl_arithmetic_op_for_Money {
 
 impl_arithmetic_op_for_Money!(Add add +);
 impl_arithmetic_op_for_Money!(Sub sub -);
-impl_arithmetic_op_for_Money!(Mul mul *);
-impl_arithmetic_op_for_Money!(Div div /);
+
+// There is no Money * Money: 300€ * 2€ has no meaning.
+
+// 100€ / 2€ = 50, an amount divided by an amount has no unit
+impl Div<Money> for Money {
+    type Output = Decimal;
+
+    fn div(self, other: Money) -> Self::Output {
+        assert_eq!(self.currency, other.currency, "{} / {}: use a Context to mix currencies", self, other);
+        self.amount / other.amount
+    }
+}
 
 // Implement Percentage operations
+// results are rounded with the default policy, use a Context to pick another one
 
 impl Add<Percentage> for Money {
     type Output = Money;
 
     fn add(self, rhs: Percentage) -> Self::Output {
-        Money {
-            amount: self.amount + (self.amount * rhs.value / 100.0),
-            currency: self.currency
-        }
+        Money::new(self.amount + self.percentage_part(rhs), self.currency).round(RoundingPolicy::default())
     }
 }
 
@@ -214,10 +231,7 @@ impl Sub<Percentage> for Money {
     type Output = Money;
 
     fn sub(self, rhs: Percentage) -> Self::Output {
-        Money {
-            amount: self.amount - (self.amount * rhs.value / 100.0),
-            currency: self.currency
-        }
+        Money::new(self.amount - self.percentage_part(rhs), self.currency).round(RoundingPolicy::default())
     }
 }
 
@@ -225,92 +239,113 @@ impl Mul<Percentage> for Money {
     type Output = Money;
 
     fn mul(self, rhs: Percentage) -> Self::Output {
-        Money {
-            amount: self.amount * rhs.value / 100.0,
-            currency: self.currency
-        }
+        Money::new(self.percentage_part(rhs), self.currency).round(RoundingPolicy::default())
     }
 }
 
 impl fmt::Display for Money {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.currency {
-            Currency::Dollars => write!(f, "${:.2}", self.amount),
-            Currency::Euros => write!(f, "{:.2}€", self.amount)         
+        let rounded = self.round(RoundingPolicy::default());
+        let amount = format!("{:.*}", usize::from(self.currency.minor_units), rounded.amount);
+        let space = if self.currency.symbol_is_word() { " " } else { "" };
+
+        if self.currency.symbol_first {
+            write!(f, "{}{}{}", self.currency, space, amount)
+        } else {
+            write!(f, "{}{}{}", amount, space, self.currency)
         }
     }
 }
 
-impl Into<f64> for Money {
-    fn into(self) -> f64 {
-        self.amount
+impl From<Money> for f64 {
+    fn from(money: Money) -> f64 {
+        money.amount.to_f64().unwrap_or(f64::NAN)
     }
 }
 
-impl Into<i32> for Money {
-    fn into(self) -> i32 {
-        self.amount as i32
+impl From<Money> for i32 {
+    fn from(money: Money) -> i32 {
+        // saturate like `as` did for f64
+        money.amount.trunc().to_i32().unwrap_or(
+            if money.amount.is_sign_negative() { i32::MIN } else { i32::MAX }
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::RateTable;
+    use crate::units::rounding::RoundingMode;
 
     #[test]
-    fn currency_from_str() {
-        assert_eq!(Currency::from_str("€"), Ok(Currency::Euros));
-        assert_eq!(Currency::from_str("$"), Ok(Currency::Dollars));
-        assert_eq!(Currency::from_str("Nothing to see here"), Err(ParseCurrencyError));
+    fn money_from_str() {
+        assert_eq!(Money::from_str("42.0€"), Ok(Money{amount: dec!(42.0), currency: Currency::EUR}));
+        assert_eq!(Money::from_str("42€"), Ok(Money{amount: dec!(42.0), currency: Currency::EUR}));
+        assert_eq!(Money::from_str("$ 13"), Ok(Money{amount: dec!(13.0), currency: Currency::USD}));
+        assert_eq!(Money::from_str("$ 13 $"), Err(ParseMoneyError::DuplicateCurrency));
+        assert_eq!(Money::from_str("EUR 12.50"), Ok(Money{amount: dec!(12.5), currency: Currency::EUR}));
+        assert_eq!(Money::from_str("£3"), Ok(Money{amount: dec!(3), currency: Currency::GBP}));
+        assert_eq!(Money::from_str("12 Swiss Franc"), Ok(Money{amount: dec!(12), currency: Currency::CHF}));
+        assert_eq!(Money::from_str("(1,234.50 €)"), Ok(Money{amount: dec!(-1234.5), currency: Currency::EUR}));
     }
 
     #[test]
-    fn money_from_str() {
-        assert_eq!(Money::from_str("42.0€"), Ok(Money{amount: 42.0, currency: Currency::Euros}));
-        assert_eq!(Money::from_str("42€"), Ok(Money{amount: 42.0, currency: Currency::Euros}));
-        assert_eq!(Money::from_str("$ 13"), Ok(Money{amount: 13.0, currency: Currency::Dollars}));
-        assert_eq!(Money::from_str("$ 13 $"), Err(ParseMoneyError));
+    fn display_minor_units() {
+        assert_eq!(Money::new(dec!(1234.5), Currency::USD).to_string(), "$1234.50");
+        assert_eq!(Money::new(dec!(1234.5), Currency::EUR).to_string(), "1234.50€");
+        assert_eq!(Money::new(dec!(1234), Currency::JPY).to_string(), "¥1234");
+        assert_eq!(Money::new(dec!(1.5), Currency::KWD).to_string(), "1.500 KWD");
+        assert_eq!(Money::new(dec!(12), Currency::CHF).to_string(), "12.00 CHF");
     }
 
 
     #[test]
-    fn add_money() {
-        let bob = Money::new(1337.0, Currency::Dollars);
-        let alice = Money::new(42.0, Currency::Euros);
-        assert_eq!(bob + alice, Money{amount: 1381.1, currency: Currency::Dollars});
+    fn convert() {
+        let rates = RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.05));
+        let alice = Money::new(dec!(42.0), Currency::EUR);
+        assert_eq!(alice.convert(Currency::USD, &rates), Ok(Money{amount: dec!(44.1), currency: Currency::USD}));
+    }
+
+    #[test]
+    #[should_panic(expected = "use a Context to mix currencies")]
+    fn add_mixed_currencies() {
+        let _ = Money::new(dec!(1337.0), Currency::USD) + Money::new(dec!(42.0), Currency::EUR);
     }
 
     #[test]
     fn arithmetic_operations() {
         assert_eq!(Money::from_str("100€").unwrap() + Money::from_str("500€").unwrap(), Money::from_str("600€").unwrap());
         assert_eq!(Money::from_str("1000€").unwrap() - Money::from_str("500€").unwrap(), Money::from_str("500€").unwrap());
-        assert_eq!(Money::from_str("300€").unwrap() * Money::from_str("2€").unwrap(), Money::from_str("600€").unwrap());
-        assert_eq!(Money::from_str("100€").unwrap() / Money::from_str("2€").unwrap(), Money::from_str("50€").unwrap());
+        assert_eq!(Money::from_str("300€").unwrap() * 2, Money::from_str("600€").unwrap());
+        assert_eq!(Money::from_str("100€").unwrap() / 2, Money::from_str("50€").unwrap());
+        assert_eq!(Money::from_str("100€").unwrap() / Money::from_str("2€").unwrap(), dec!(50));
     }
 
     #[test]
     fn add_i32() {
-        assert_eq!(Money::new(42.0, Currency::Euros) + 12, Money {amount: 54.0, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(42.0), Currency::EUR) + 12, Money {amount: dec!(54.0), currency: Currency::EUR});
     }
     
     #[test]
     fn mul_i32() {
-        assert_eq!(Money::new(42.0, Currency::Euros) * 12, Money {amount: 504.0, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(42.0), Currency::EUR) * 12, Money {amount: dec!(504.0), currency: Currency::EUR});
     }
 
     #[test]
     fn add_f64() {
-        assert_eq!(Money::new(7.0, Currency::Euros) + f64::from(6.0), Money {amount: 13.0, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(7.0), Currency::EUR) + 6.0, Money {amount: dec!(13.0), currency: Currency::EUR});
     }
 
     #[test]
     fn mul_f64() {
-        assert_eq!(Money::new(7.0, Currency::Euros) * 6.0, Money {amount: 42.0, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(7.0), Currency::EUR) * 6.0, Money {amount: dec!(42.0), currency: Currency::EUR});
     }
 
     #[test]
     fn add_percent() {
-        assert_eq!(Money::new(42.0, Currency::Euros) + Percentage::new(12.0), Money {amount: 47.04, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(42.0), Currency::EUR) + Percentage::new(12.0), Money {amount: dec!(47.04), currency: Currency::EUR});
     }
 
     #[test]
@@ -318,8 +353,75 @@ mod tests {
         assert_eq!(Money::from_str("100€").unwrap() - Percentage::new(12.0), Money::from_str("88€").unwrap());
     }
 
+    #[test]
+    fn exact_sum() {
+        let cents = (0..10).fold(Money::from_str("0€").unwrap(), |acc, _| acc + Money::from_str("0.10€").unwrap());
+        assert_eq!(cents, Money::from_str("1€").unwrap());
+        assert_eq!(Money::new(dec!(0.1), Currency::EUR) + 0.2, Money::new(dec!(0.3), Currency::EUR));
+    }
+
+    #[test]
+    fn money_as_map_key() {
+        use std::collections::HashMap;
+
+        let mut totals = HashMap::new();
+        totals.insert(Money::from_str("42.0€").unwrap(), "answer");
+        assert_eq!(totals.get(&Money::from_str("42€").unwrap()), Some(&"answer"));
+
+        let mut sorted = [
+            Money::from_str("$ 3").unwrap(),
+            Money::from_str("10€").unwrap(),
+            Money::from_str("2€").unwrap(),
+        ];
+        sorted.sort_by(Money::cmp_by_currency);
+        let keys: Vec<String> = sorted.iter().map(|m| m.to_string()).collect();
+        assert_eq!(keys, ["2.00€", "10.00€", "$3.00"]);
+    }
+
+    #[test]
+    fn percentage_is_rounded() {
+        assert_eq!(Money::new(dec!(10.01), Currency::EUR) * Percentage::new(50.0), Money::new(dec!(5.01), Currency::EUR));
+        assert_eq!(Money::new(dec!(10.01), Currency::EUR) - Percentage::new(50.0), Money::new(dec!(5.01), Currency::EUR));
+    }
+
+    #[test]
+    fn round() {
+        let price = Money::new(dec!(12.3449), Currency::CHF);
+        assert_eq!(price.round(RoundingPolicy::default()), Money::new(dec!(12.34), Currency::CHF));
+        assert_eq!(price.round(RoundingPolicy::cash(dec!(0.05))), Money::new(dec!(12.35), Currency::CHF));
+        assert_eq!(price.round(RoundingPolicy::new(RoundingMode::Ceiling)), Money::new(dec!(12.35), Currency::CHF));
+        assert_eq!(Money::new(dec!(0.125), Currency::EUR).to_string(), "0.13€");
+    }
+
+    #[test]
+    fn conversion_is_rounded() {
+        let rates = RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.0921));
+        let price = Money::new(dec!(9.98), Currency::EUR);
+        assert_eq!(price.convert(Currency::USD, &rates), Ok(Money::new(dec!(10.90), Currency::USD)));
+        assert_eq!(
+            price.convert_with(Currency::USD, &rates, RoundingPolicy::new(RoundingMode::Floor)),
+            Ok(Money::new(dec!(10.89), Currency::USD))
+        );
+    }
+
+    #[test]
+    fn checked() {
+        let money = Money::new(dec!(12), Currency::EUR);
+        assert_eq!(money.checked_add(money), Ok(Money::new(dec!(24), Currency::EUR)));
+        assert_eq!(money.checked_sub(money), Ok(Money::new(dec!(0), Currency::EUR)));
+        assert_eq!(money.checked_mul(dec!(2)), Ok(Money::new(dec!(24), Currency::EUR)));
+        assert_eq!(money.checked_div(dec!(0)), Err(UnitError::DivisionByZero));
+        assert_eq!(money.checked_div_money(Money::new(dec!(4), Currency::EUR)), Ok(dec!(3)));
+        assert_eq!(money.checked_div_money(Money::new(dec!(0), Currency::EUR)), Err(UnitError::DivisionByZero));
+        assert_eq!(Money::new(Decimal::MAX, Currency::EUR).checked_mul(dec!(2)), Err(UnitError::Overflow));
+        assert_eq!(
+            money.checked_add(Money::new(dec!(3), Currency::USD)),
+            Err(UnitError::IncompatibleUnits("12.00€ + $3.00".to_owned()))
+        );
+    }
+
     #[test]
     fn percentage_of() {
-        assert_eq!(Money::new(42.0, Currency::Euros) * Percentage::new(12.0), Money {amount: 5.04, currency: Currency::Euros});
+        assert_eq!(Money::new(dec!(42.0), Currency::EUR) * Percentage::new(12.0), Money {amount: dec!(5.04), currency: Currency::EUR});
     }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, Sub, Mul};
+use rust_decimal::Decimal;
+
+use super::context::Context;
+use super::currency::Currency;
+use super::error::UnitError;
+use super::money::Money;
+use super::rounding::RoundingPolicy;
+use crate::Percentage;
+
+/*
+    Money Bag
+
+    Holds one subtotal per currency and never converts on its own:
+    10€ + $5 stays 10€ + $5 until it is collapsed with exchange rates.
+    Currencies whose subtotal drops to zero are removed.
+*/
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Default)]
+pub struct MoneyBag {
+    amounts: BTreeMap<Currency, Decimal>
+}
+
+impl MoneyBag {
+    pub fn new() -> MoneyBag {
+        MoneyBag::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amounts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.amounts.len()
+    }
+
+    // Subtotal in `currency`, zero when the bag has none
+    pub fn get(&self, currency: Currency) -> Money {
+        Money::new(self.amounts.get(&currency).copied().unwrap_or_default(), currency)
+    }
+
+    // Subtotals, sorted by currency code
+    pub fn iter(&self) -> impl Iterator<Item = Money> + '_ {
+        self.amounts.iter().map(|(&currency, &amount)| Money::new(amount, currency))
+    }
+
+    // The only way out of the bag: convert every subtotal and add them up
+    pub fn collapse(&self, currency: Currency, ctx: &Context) -> Result<Money, UnitError> {
+        self.iter().try_fold(Money::new(Decimal::ZERO, currency), |total, money| {
+            total.checked_add(ctx.convert(money, currency)?)
+        })
+    }
+
+    /*
+        Checked arithmetic
+
+        Same operations as the operators, but an overflowing subtotal or
+        a NaN or infinite percentage is an error instead of a panic.
+        The bag is left as it was when `checked_put` fails.
+    */
+    pub fn checked_put(&mut self, money: Money) -> Result<(), UnitError> {
+        let current = self.amounts.get(&money.currency).copied().unwrap_or_default();
+        let amount = current.checked_add(money.amount).ok_or(UnitError::Overflow)?;
+
+        if amount.is_zero() {
+            self.amounts.remove(&money.currency);
+        } else {
+            self.amounts.insert(money.currency, amount);
+        }
+        Ok(())
+    }
+
+    pub fn checked_add(mut self, rhs: MoneyBag) -> Result<MoneyBag, UnitError> {
+        for money in rhs.iter() {
+            self.checked_put(money)?;
+        }
+        Ok(self)
+    }
+
+    pub fn checked_sub(mut self, rhs: MoneyBag) -> Result<MoneyBag, UnitError> {
+        for money in rhs.iter() {
+            self.checked_put(Money::new(-money.amount, money.currency))?;
+        }
+        Ok(self)
+    }
+
+    pub fn checked_add_percentage(self, rhs: Percentage) -> Result<MoneyBag, UnitError> {
+        self.try_map(|money| money.checked_add(percentage_part(money, rhs)?))
+    }
+
+    pub fn checked_sub_percentage(self, rhs: Percentage) -> Result<MoneyBag, UnitError> {
+        self.try_map(|money| money.checked_sub(percentage_part(money, rhs)?))
+    }
+
+    pub fn checked_mul_percentage(self, rhs: Percentage) -> Result<MoneyBag, UnitError> {
+        self.try_map(|money| percentage_part(money, rhs))
+    }
+
+    fn put(&mut self, money: Money) {
+        self.checked_put(money).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Rounded like Money ± Percentage
+    fn try_map(self, f: impl Fn(Money) -> Result<Money, UnitError>) -> Result<MoneyBag, UnitError> {
+        self.iter().map(|money| Ok(f(money)?.round(RoundingPolicy::default()))).collect()
+    }
+}
+
+fn percentage_part(money: Money, percentage: Percentage) -> Result<Money, UnitError> {
+    Ok(Money::new(money.checked_percentage_part(percentage)?, money.currency))
+}
+
+impl From<Money> for MoneyBag {
+    fn from(money: Money) -> MoneyBag {
+        let mut bag = MoneyBag::new();
+        bag.put(money);
+        bag
+    }
+}
+
+impl FromIterator<Money> for MoneyBag {
+    fn from_iter<I: IntoIterator<Item = Money>>(iter: I) -> MoneyBag {
+        let mut bag = MoneyBag::new();
+        for money in iter {
+            bag.put(money);
+        }
+        bag
+    }
+}
+
+impl Add<Money> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn add(mut self, rhs: Money) -> Self::Output {
+        self.put(rhs);
+        self
+    }
+}
+
+impl Sub<Money> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn sub(mut self, rhs: Money) -> Self::Output {
+        self.put(Money::new(-rhs.amount, rhs.currency));
+        self
+    }
+}
+
+impl Add<MoneyBag> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn add(self, rhs: MoneyBag) -> Self::Output {
+        rhs.iter().fold(self, |bag, money| bag + money)
+    }
+}
+
+impl Sub<MoneyBag> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn sub(self, rhs: MoneyBag) -> Self::Output {
+        rhs.iter().fold(self, |bag, money| bag - money)
+    }
+}
+
+// Percentages apply to every subtotal
+impl Add<Percentage> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn add(self, rhs: Percentage) -> Self::Output {
+        self.checked_add_percentage(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl Sub<Percentage> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn sub(self, rhs: Percentage) -> Self::Output {
+        self.checked_sub_percentage(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl Mul<Percentage> for MoneyBag {
+    type Output = MoneyBag;
+
+    fn mul(self, rhs: Percentage) -> Self::Output {
+        self.checked_mul_percentage(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+// 12.00€ + $3.00
+impl fmt::Display for MoneyBag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "0");
+        }
+
+        let parts: Vec<String> = self.iter().map(|money| money.to_string()).collect();
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::exchange::{ExchangeError, RateTable};
+    use crate::units::testing::{eur, usd};
+
+    #[test]
+    fn no_conversion() {
+        let bag = MoneyBag::from(eur(dec!(10))) + usd(dec!(5)) + eur(dec!(2.5));
+
+        assert_eq!(bag.len(), 2);
+        assert_eq!(bag.get(Currency::EUR), eur(dec!(12.5)));
+        assert_eq!(bag.get(Currency::USD), usd(dec!(5)));
+        assert_eq!(bag.get(Currency::GBP), Money::new(dec!(0), Currency::GBP));
+        assert_eq!(bag.to_string(), "12.50€ + $5.00");
+    }
+
+    #[test]
+    fn sub() {
+        let bag = MoneyBag::from(eur(dec!(10))) + usd(dec!(5)) - usd(dec!(5));
+        assert_eq!(bag, MoneyBag::from(eur(dec!(10))));
+
+        let empty = bag.clone() - bag;
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_string(), "0");
+    }
+
+    #[test]
+    fn percentage() {
+        let bag = MoneyBag::from(eur(dec!(10))) + usd(dec!(5));
+        assert_eq!(bag.clone() * Percentage::new(10.0), MoneyBag::from(eur(dec!(1))) + usd(dec!(0.5)));
+        assert_eq!(bag + Percentage::new(10.0), MoneyBag::from(eur(dec!(11))) + usd(dec!(5.5)));
+    }
+
+    #[test]
+    fn collapse() {
+        let bag = MoneyBag::from(eur(dec!(10))) + usd(dec!(5.25));
+
+        let rates = RateTable::new().with_rate(Currency::EUR, Currency::USD, dec!(1.05));
+        assert_eq!(bag.collapse(Currency::EUR, &Context::new(&rates)), Ok(eur(dec!(15))));
+        assert_eq!(
+            bag.collapse(Currency::EUR, &Context::default()),
+            Err(UnitError::UnknownConversion(ExchangeError::NoRate { from: Currency::USD, to: Currency::EUR }))
+        );
+        assert_eq!(
+            (MoneyBag::from(eur(Decimal::MAX)) + usd(dec!(1))).collapse(Currency::EUR, &Context::new(&rates)),
+            Err(UnitError::Overflow)
+        );
+    }
+
+    #[test]
+    fn checked() {
+        let bag = MoneyBag::from(eur(dec!(10))) + usd(dec!(5));
+        assert_eq!(bag.clone().checked_add(MoneyBag::from(eur(dec!(1)))), Ok(MoneyBag::from(eur(dec!(11))) + usd(dec!(5))));
+        assert_eq!(bag.clone().checked_sub(bag.clone()), Ok(MoneyBag::new()));
+        assert_eq!(bag.clone().checked_add_percentage(Percentage::new(10.0)), Ok(bag.clone() + Percentage::new(10.0)));
+        assert_eq!(bag.clone().checked_mul_percentage(Percentage::new(10.0)), Ok(bag.clone() * Percentage::new(10.0)));
+
+        let mut full = MoneyBag::from(eur(Decimal::MAX));
+        assert_eq!(full.checked_put(eur(dec!(1))), Err(UnitError::Overflow));
+        assert_eq!(full.get(Currency::EUR), eur(Decimal::MAX));
+        assert_eq!(full.clone().checked_add(full.clone()), Err(UnitError::Overflow));
+        assert_eq!(full.checked_add_percentage(Percentage::new(50.0)), Err(UnitError::Overflow));
+        assert_eq!(bag.clone().checked_sub_percentage(Percentage::new(f64::NAN)), Err(UnitError::NaN));
+        assert_eq!(bag.checked_mul_percentage(Percentage::new(f64::INFINITY)), Err(UnitError::Overflow));
+    }
+}
@@ -0,0 +1,393 @@
+use std::fmt;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::currency::Currency;
+use super::format::Locale;
+use super::money::Money;
+use crate::Percentage;
+
+// What went wrong while parsing Money
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParseMoneyError {
+    Empty,
+    MissingAmount,
+    MissingCurrency,
+    // "$ 13 $"
+    DuplicateCurrency,
+    UnknownCurrency(String),
+    InvalidAmount(String),
+    UnbalancedParentheses
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoneyError::Empty => write!(f, "empty input"),
+            ParseMoneyError::MissingAmount => write!(f, "no amount found"),
+            ParseMoneyError::MissingCurrency => write!(f, "no currency found"),
+            ParseMoneyError::DuplicateCurrency => write!(f, "currency given on both sides of the amount"),
+            ParseMoneyError::UnknownCurrency(c) => write!(f, "unknown currency {:?}", c),
+            ParseMoneyError::InvalidAmount(a) => write!(f, "invalid amount {:?}", a),
+            ParseMoneyError::UnbalancedParentheses => write!(f, "unbalanced parentheses"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+/*
+    Money parser for real world input
+
+    -12€, EUR 12.50, 1,234.56 $, 12,50 €, ($45.00)
+
+    Without a locale the decimal separator is guessed: with both '.'
+    and ',' the last one wins, a single separator followed by exactly
+    three digits groups thousands, anything else is the decimal point.
+    Three digits are decimals after a zero (0.125) and in a currency
+    with three minor units (1.500 KWD), so Display output reads back.
+    Parentheses mean a negative amount (accounting style).
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct MoneyParser {
+    pub locale: Option<Locale>,
+    pub default_currency: Option<Currency>
+}
+
+impl MoneyParser {
+    pub fn new() -> MoneyParser {
+        MoneyParser::default()
+    }
+
+    pub fn with_locale(self, locale: Locale) -> MoneyParser {
+        MoneyParser { locale: Some(locale), ..self }
+    }
+
+    // Used when the input has no currency: "(45.00)"
+    pub fn with_default_currency(self, currency: Currency) -> MoneyParser {
+        MoneyParser { default_currency: Some(currency), ..self }
+    }
+
+    pub fn parse(&self, s: &str) -> Result<Money, ParseMoneyError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseMoneyError::Empty);
+        }
+
+        let (s, parenthesized) = match (s.strip_prefix('('), s.strip_suffix(')')) {
+            (Some(_), Some(_)) => (s[1..s.len() - 1].trim(), true),
+            (None, None) => (s, false),
+            _ => return Err(ParseMoneyError::UnbalancedParentheses),
+        };
+
+        // split "EUR -12.50" into "EUR -", "12.50" and ""
+        let mut amount_start = s.find(|c: char| c.is_ascii_digit()).ok_or(ParseMoneyError::MissingAmount)?;
+        let amount_end = s.rfind(|c: char| c.is_ascii_digit()).ok_or(ParseMoneyError::MissingAmount)? + 1;
+
+        // ".50€"
+        if s[..amount_start].ends_with(['.', ',']) {
+            amount_start -= 1;
+        }
+
+        let (prefix, negative) = take_sign(&s[..amount_start])?;
+        let suffix = s[amount_end..].trim();
+
+        let currency = match (prefix.is_empty(), suffix.is_empty()) {
+            // Currency is at the start: $47.0
+            (false, true) => parse_currency(prefix)?,
+            // Currency is at the end: 12.0€
+            (true, false) => parse_currency(suffix)?,
+            (true, true) => self.default_currency.ok_or(ParseMoneyError::MissingCurrency)?,
+            (false, false) => return Err(ParseMoneyError::DuplicateCurrency),
+        };
+
+        let amount = self.parse_amount(&s[amount_start..amount_end], currency)?;
+
+        if negative && parenthesized {
+            return Err(ParseMoneyError::InvalidAmount(s.to_owned()));
+        }
+
+        Ok(Money::new(if negative || parenthesized { -amount } else { amount }, currency))
+    }
+
+    fn parse_amount(&self, s: &str, currency: Currency) -> Result<Decimal, ParseMoneyError> {
+        parse_number(s, self.locale, Some(currency.minor_units)).ok_or_else(|| ParseMoneyError::InvalidAmount(s.to_owned()))
+    }
+}
+
+// Unsigned number with grouping, the separators come from the locale or are guessed
+// with the minor units of the currency, if any
+fn parse_number(s: &str, locale: Option<Locale>, minor_units: Option<u8>) -> Option<Decimal> {
+    let is_space = |c: char| c == ' ' || c == '\u{a0}' || c == '\u{202f}';
+    if s.chars().any(|c| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '\'') || is_space(c))) {
+        return None;
+    }
+
+    let (decimal_separator, grouping_separator) = match locale {
+        Some(locale) => (Some(locale.decimal_separator), locale.grouping_separator),
+        None => guess_separators(s, minor_units),
+    };
+
+    let mut normalized = String::new();
+    for c in s.chars() {
+        if Some(c) == decimal_separator {
+            normalized.push('.');
+        } else if c.is_ascii_digit() {
+            normalized.push(c);
+        } else if !(c == grouping_separator || c == '\'' || is_space(c)) {
+            return None;
+        }
+    }
+
+    Decimal::from_str(&normalized).ok()
+}
+
+// Remove the sign from the text before the amount: "-$", "EUR -"
+fn take_sign(prefix: &str) -> Result<(&str, bool), ParseMoneyError> {
+    let minus = prefix.matches('-').count();
+    let plus = prefix.matches('+').count();
+    if minus + plus > 1 {
+        return Err(ParseMoneyError::InvalidAmount(prefix.to_owned()));
+    }
+
+    let currency = prefix.trim().trim_matches(['-', '+']).trim();
+    if currency.contains(['-', '+']) {
+        return Err(ParseMoneyError::InvalidAmount(prefix.to_owned()));
+    }
+
+    Ok((currency, minus == 1))
+}
+
+fn parse_currency(s: &str) -> Result<Currency, ParseMoneyError> {
+    Currency::from_str(s).map_err(|_| ParseMoneyError::UnknownCurrency(s.to_owned()))
+}
+
+// (decimal separator, grouping separator)
+fn guess_separators(s: &str, minor_units: Option<u8>) -> (Option<char>, char) {
+    let last_dot = s.rfind('.');
+    let last_comma = s.rfind(',');
+
+    match (last_dot, last_comma) {
+        (Some(dot), Some(comma)) if dot > comma => (Some('.'), ','),
+        (Some(_), Some(_)) => (Some(','), '.'),
+        (None, None) => (None, ','),
+        (Some(i), None) | (None, Some(i)) => {
+            let separator = s[i..].chars().next().unwrap();
+            let other = if separator == '.' { ',' } else { '.' };
+            let digits_after = s.len() - i - 1;
+            let zero = s[..i].chars().all(|c| c == '0' || c.is_whitespace());
+            let thousands = digits_after == 3 && !zero && minor_units != Some(3);
+
+            if s.matches(separator).count() > 1 || thousands {
+                (None, separator)
+            } else {
+                (Some(separator), other)
+            }
+        },
+    }
+}
+
+// What went wrong while parsing a Percentage
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParsePercentageError {
+    Empty,
+    // "12.5", is it 12.5% or 1250%?
+    MissingPercentSign,
+    // "1,250%" without a locale, is it 1250% or 1.25%?
+    Ambiguous(String),
+    InvalidNumber(String)
+}
+
+impl fmt::Display for ParsePercentageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePercentageError::Empty => write!(f, "empty input"),
+            ParsePercentageError::MissingPercentSign => write!(f, "no percent sign found"),
+            ParsePercentageError::Ambiguous(n) => write!(f, "ambiguous number {:?}, give a locale", n),
+            ParsePercentageError::InvalidNumber(n) => write!(f, "invalid number {:?}", n),
+        }
+    }
+}
+
+impl std::error::Error for ParsePercentageError {}
+
+/*
+    Percentage parser
+
+    12%, 12.5 %, -3%, +0.5%, 12,5 %
+
+    The percent sign is required. Without a locale a lone '.' is always
+    the decimal point, so 0.125% and 12.345% keep their decimals.
+    A lone ',' is the decimal comma, unless it could group thousands:
+    1,250% is an error without a locale, it is 1250% or 1.25%.
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct PercentageParser {
+    pub locale: Option<Locale>
+}
+
+impl PercentageParser {
+    pub fn new() -> PercentageParser {
+        PercentageParser::default()
+    }
+
+    pub fn with_locale(self, locale: Locale) -> PercentageParser {
+        PercentageParser { locale: Some(locale) }
+    }
+
+    pub fn parse(&self, s: &str) -> Result<Percentage, ParsePercentageError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParsePercentageError::Empty);
+        }
+
+        let number = s.strip_suffix('%').ok_or(ParsePercentageError::MissingPercentSign)?.trim_end();
+        let invalid = || ParsePercentageError::InvalidNumber(number.to_owned());
+
+        let (digits, negative) = match number.strip_prefix('-') {
+            Some(digits) => (digits, true),
+            None => (number.strip_prefix('+').unwrap_or(number), false),
+        };
+
+        let digits = digits.trim_start();
+        let locale = match self.locale {
+            None if !digits.contains(',') => Some(Locale::EN_US),
+            None if digits.matches(',').count() == 1 && guess_separators(digits, None) == (None, ',') => {
+                return Err(ParsePercentageError::Ambiguous(digits.to_owned()));
+            },
+            locale => locale,
+        };
+
+        let value = parse_number(digits, locale, None).ok_or_else(invalid)?;
+        let value = value.to_f64().ok_or_else(invalid)?;
+
+        Ok(Percentage::new(if negative { -value } else { value }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn parse(s: &str) -> Result<Money, ParseMoneyError> {
+        MoneyParser::new().parse(s)
+    }
+
+    #[test]
+    fn signs() {
+        assert_eq!(parse("-12€"), Ok(Money::new(dec!(-12), Currency::EUR)));
+        assert_eq!(parse("-$5.00"), Ok(Money::new(dec!(-5), Currency::USD)));
+        assert_eq!(parse("$ -5"), Ok(Money::new(dec!(-5), Currency::USD)));
+        assert_eq!(parse("+3 CHF"), Ok(Money::new(dec!(3), Currency::CHF)));
+        assert_eq!(parse("--3 CHF"), Err(ParseMoneyError::InvalidAmount("--".to_owned())));
+    }
+
+    #[test]
+    fn codes() {
+        assert_eq!(parse("EUR 12.50"), Ok(Money::new(dec!(12.5), Currency::EUR)));
+        assert_eq!(parse("12.50 gbp"), Ok(Money::new(dec!(12.5), Currency::GBP)));
+        assert_eq!(parse("12 XYZ"), Err(ParseMoneyError::UnknownCurrency("XYZ".to_owned())));
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(parse("1,234.56 $"), Ok(Money::new(dec!(1234.56), Currency::USD)));
+        assert_eq!(parse("12,50 €"), Ok(Money::new(dec!(12.5), Currency::EUR)));
+        assert_eq!(parse("1.234,56 €"), Ok(Money::new(dec!(1234.56), Currency::EUR)));
+        assert_eq!(parse("$1,234,567"), Ok(Money::new(dec!(1234567), Currency::USD)));
+        assert_eq!(parse("CHF 1'234.50"), Ok(Money::new(dec!(1234.5), Currency::CHF)));
+        assert_eq!(parse(".50€"), Ok(Money::new(dec!(0.5), Currency::EUR)));
+        assert_eq!(parse("1.2.3,4.5 €"), Err(ParseMoneyError::InvalidAmount("1.2.3,4.5".to_owned())));
+    }
+
+    #[test]
+    fn three_digits_after_the_separator() {
+        assert_eq!(parse("0.125 €"), Ok(Money::new(dec!(0.125), Currency::EUR)));
+        assert_eq!(parse(",125 €"), Ok(Money::new(dec!(0.125), Currency::EUR)));
+        assert_eq!(parse("1.500 KWD"), Ok(Money::new(dec!(1.5), Currency::KWD)));
+        assert_eq!(parse("1.500 €"), Ok(Money::new(dec!(1500), Currency::EUR)));
+        assert_eq!(parse("¥1,500"), Ok(Money::new(dec!(1500), Currency::JPY)));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let amounts = [
+            Money::new(dec!(1500), Currency::JPY),
+            Money::new(dec!(-42), Currency::JPY),
+            Money::new(dec!(12.34), Currency::EUR),
+            Money::new(dec!(1234.5), Currency::USD),
+            Money::new(dec!(-0.5), Currency::GBP),
+            Money::new(dec!(1.5), Currency::KWD),
+            Money::new(dec!(0.125), Currency::KWD),
+            Money::new(dec!(1234.567), Currency::BHD),
+        ];
+        for money in amounts {
+            assert_eq!(parse(&money.to_string()), Ok(money), "{}", money);
+        }
+    }
+
+    #[test]
+    fn locale_decimal_comma() {
+        let de = MoneyParser::new().with_locale(Locale::DE_DE);
+        assert_eq!(de.parse("1,234 €"), Ok(Money::new(dec!(1.234), Currency::EUR)));
+        assert_eq!(de.parse("1.234 €"), Ok(Money::new(dec!(1234), Currency::EUR)));
+
+        let us = MoneyParser::new().with_locale(Locale::EN_US);
+        assert_eq!(us.parse("$1,234"), Ok(Money::new(dec!(1234), Currency::USD)));
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(parse("($45.00)"), Ok(Money::new(dec!(-45), Currency::USD)));
+        assert_eq!(parse("(45.00)"), Err(ParseMoneyError::MissingCurrency));
+        assert_eq!(parse("(45.00"), Err(ParseMoneyError::UnbalancedParentheses));
+
+        let eur = MoneyParser::new().with_default_currency(Currency::EUR);
+        assert_eq!(eur.parse("(45.00)"), Ok(Money::new(dec!(-45), Currency::EUR)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("  "), Err(ParseMoneyError::Empty));
+        assert_eq!(parse("€"), Err(ParseMoneyError::MissingAmount));
+        assert_eq!(parse("$ 13 $"), Err(ParseMoneyError::DuplicateCurrency));
+        assert_eq!(parse("12e5 €"), Err(ParseMoneyError::InvalidAmount("12e5".to_owned())));
+        assert_eq!(ParseMoneyError::UnknownCurrency("XYZ".to_owned()).to_string(), "unknown currency \"XYZ\"");
+    }
+
+    #[test]
+    fn percentages() {
+        let parser = PercentageParser::new();
+        assert_eq!(parser.parse("12%"), Ok(Percentage::new(12.0)));
+        assert_eq!(parser.parse(" 12.5 %"), Ok(Percentage::new(12.5)));
+        assert_eq!(parser.parse("-3%"), Ok(Percentage::new(-3.0)));
+        assert_eq!(parser.parse("+0.25%"), Ok(Percentage::new(0.25)));
+        assert_eq!(parser.parse("12,5%"), Ok(Percentage::new(12.5)));
+        assert_eq!(parser.parse("0,125%"), Ok(Percentage::new(0.125)));
+        assert_eq!(parser.parse("1,250,000%"), Ok(Percentage::new(1250000.0)));
+        assert_eq!(parser.parse("0.125%"), Ok(Percentage::new(0.125)));
+        assert_eq!(parser.parse("12.345 %"), Ok(Percentage::new(12.345)));
+        assert_eq!(parser.parse("1,234.567%"), Ok(Percentage::new(1234.567)));
+
+        let de = PercentageParser::new().with_locale(Locale::DE_DE);
+        assert_eq!(de.parse("12,5 %"), Ok(Percentage::new(12.5)));
+        assert_eq!(de.parse("1.250 %"), Ok(Percentage::new(1250.0)));
+        assert_eq!(de.parse("1,250 %"), Ok(Percentage::new(1.25)));
+        assert_eq!(PercentageParser::new().with_locale(Locale::EN_US).parse("1,250%"), Ok(Percentage::new(1250.0)));
+    }
+
+    #[test]
+    fn percentage_errors() {
+        let parser = PercentageParser::new();
+        assert_eq!(parser.parse(""), Err(ParsePercentageError::Empty));
+        assert_eq!(parser.parse("12.5"), Err(ParsePercentageError::MissingPercentSign));
+        assert_eq!(parser.parse("%"), Err(ParsePercentageError::InvalidNumber("".to_owned())));
+        assert_eq!(parser.parse("--3%"), Err(ParsePercentageError::InvalidNumber("--3".to_owned())));
+        assert_eq!(parser.parse("abc%"), Err(ParsePercentageError::InvalidNumber("abc".to_owned())));
+        assert_eq!(parser.parse("1,250%"), Err(ParsePercentageError::Ambiguous("1,250".to_owned())));
+        assert_eq!(parser.parse("-1,250 %"), Err(ParsePercentageError::Ambiguous("1,250".to_owned())));
+        assert_eq!(ParsePercentageError::MissingPercentSign.to_string(), "no percent sign found");
+    }
+}
@@ -1,5 +1,11 @@
 use std::fmt;
 use std::ops::{Add, Sub, Mul, Div};
+use std::str::FromStr;
+use rust_decimal::Decimal;
+
+use super::error::{UnitError, checked_decimal, finite};
+use super::parse::PercentageParser;
+pub use super::parse::ParsePercentageError;
 
 // Percentage
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -9,7 +15,62 @@ pub struct Percentage {
 
 impl Percentage {
     pub fn new(value: f64) -> Percentage {
-        Percentage { value: value}
+        Percentage { value }
+    }
+
+    // Checked arithmetic, errors instead of inf and NaN
+    pub fn checked_add(self, rhs: Percentage) -> Result<Percentage, UnitError> {
+        Ok(Percentage::new(finite(self.value + rhs.value)?))
+    }
+
+    pub fn checked_sub(self, rhs: Percentage) -> Result<Percentage, UnitError> {
+        Ok(Percentage::new(finite(self.value - rhs.value)?))
+    }
+
+    // 50% of 10% = 5%
+    pub fn checked_mul(self, rhs: Percentage) -> Result<Percentage, UnitError> {
+        Ok(Percentage::new(finite(self.value * rhs.value / 100.0)?))
+    }
+
+    // 13% / 2% = 6.5, a plain ratio
+    pub fn checked_div(self, rhs: Percentage) -> Result<f64, UnitError> {
+        if rhs.value == 0.0 {
+            return Err(UnitError::DivisionByZero);
+        }
+        finite(self.value / rhs.value)
+    }
+
+    /*
+        Points or compounding
+
+        15% + 22% adds percentage points: 37%.
+        Two successive changes compound instead:
+        +10% then +10% is +21%, -50% then +50% is -25%.
+    */
+    pub fn add_points(self, rhs: Percentage) -> Percentage {
+        self + rhs
+    }
+
+    pub fn sub_points(self, rhs: Percentage) -> Percentage {
+        self - rhs
+    }
+
+    pub fn compound(self, rhs: Percentage) -> Result<Percentage, UnitError> {
+        let factor = (1.0 + self.value / 100.0) * (1.0 + rhs.value / 100.0);
+        Ok(Percentage::new(finite((factor - 1.0) * 100.0)?))
+    }
+
+    // The single change equivalent to all of `changes` in a row, 0% for none
+    pub fn compound_all<I: IntoIterator<Item = Percentage>>(changes: I) -> Result<Percentage, UnitError> {
+        changes.into_iter().try_fold(Percentage::new(0.0), Percentage::compound)
+    }
+}
+
+impl FromStr for Percentage {
+    type Err = ParsePercentageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PercentageParser::new().parse(s)
     }
 }
 
@@ -43,10 +104,27 @@ impl Add<Percentage> for Percentage {
     }
 }
 */
+// + and - work on percentage points
 impl_arithmetic_op_for_Percentage!(Add add +);
 impl_arithmetic_op_for_Percentage!(Sub sub -);
-impl_arithmetic_op_for_Percentage!(Mul mul *);
-impl_arithmetic_op_for_Percentage!(Div div /);
+
+// -7% * -2% = 0.14%
+impl Mul<Percentage> for Percentage {
+    type Output = Percentage;
+
+    fn mul(self, rhs: Percentage) -> Self::Output {
+        Percentage::new(self.value * rhs.value / 100.0)
+    }
+}
+
+// 13% / 2% = 6.5
+impl Div<Percentage> for Percentage {
+    type Output = f64;
+
+    fn div(self, rhs: Percentage) -> Self::Output {
+        self.value / rhs.value
+    }
+}
 
 
 impl Add<f64> for Percentage {
@@ -98,15 +176,24 @@ impl Mul<Percentage> for f64 {
 }
 
 
-impl Into<f64> for Percentage {
-    fn into(self) -> f64 {
-        self.value
+impl From<Percentage> for f64 {
+    fn from(percentage: Percentage) -> f64 {
+        percentage.value
+    }
+}
+
+// 12% -> 12, a NaN or infinite percentage has no Decimal
+impl TryFrom<Percentage> for Decimal {
+    type Error = UnitError;
+
+    fn try_from(percentage: Percentage) -> Result<Decimal, UnitError> {
+        checked_decimal(percentage.value)
     }
 }
 
-impl Into<i32> for Percentage {
-    fn into(self) -> i32 {
-        self.value as i32
+impl From<Percentage> for i32 {
+    fn from(percentage: Percentage) -> i32 {
+        percentage.value as i32
     }
 }
 
@@ -125,11 +212,56 @@ mod tests {
     }
 
     #[test]
-    fn mul() { assert_eq!(Percentage::new(-7.0) * Percentage::new(-2.0), Percentage { value: 14.0})}
+    fn mul() {
+        assert_eq!(Percentage::new(-7.0) * Percentage::new(-2.0), Percentage { value: 0.14});
+        assert_eq!(Percentage::new(50.0) * Percentage::new(10.0), Percentage { value: 5.0});
+        assert_eq!(Percentage::new(100.0) * Percentage::new(12.0), Percentage { value: 12.0});
+    }
 
     #[test]
-    fn div() { assert_eq!(Percentage::new(13.0) / Percentage::new(2.0), Percentage { value: 6.5})}
+    fn div() {
+        assert_eq!(Percentage::new(13.0) / Percentage::new(2.0), 6.5);
+        assert_eq!(Percentage::new(5.0).checked_div(Percentage::new(20.0)), Ok(0.25));
+    }
+
+    #[test]
+    fn points_and_compounding() {
+        assert_eq!(Percentage::new(10.0).add_points(Percentage::new(10.0)), Percentage::new(20.0));
+        assert_eq!(Percentage::new(10.0).sub_points(Percentage::new(2.5)), Percentage::new(7.5));
+
+        assert!((Percentage::new(10.0).compound(Percentage::new(10.0)).unwrap().value - 21.0).abs() < 1e-9);
+        assert_eq!(Percentage::new(-50.0).compound(Percentage::new(50.0)), Ok(Percentage::new(-25.0)));
 
-    // #[test]
-    // fn div_zero() { assert_eq!(Percentage::new(13.0) / Percentage::new(0.0), Percentage { value: 0.0})}
+        let changes = [Percentage::new(-15.0), Percentage::new(-2.0), Percentage::new(19.0)];
+        assert!((Percentage::compound_all(changes).unwrap().value - -0.8730).abs() < 1e-9);
+        assert_eq!(Percentage::compound_all([]), Ok(Percentage::new(0.0)));
+
+        assert_eq!(Percentage::new(f64::MAX).compound(Percentage::new(f64::MAX)), Err(UnitError::Overflow));
+        assert_eq!(Percentage::compound_all([Percentage::new(10.0), Percentage::new(f64::NAN)]), Err(UnitError::NaN));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Percentage::from_str("12%"), Ok(Percentage::new(12.0)));
+        assert_eq!("12,5 %".parse::<Percentage>(), Ok(Percentage::new(12.5)));
+        assert_eq!(Percentage::from_str(&Percentage::new(-3.5).to_string()), Ok(Percentage::new(-3.5)));
+        assert_eq!(Percentage::from_str("12"), Err(ParsePercentageError::MissingPercentSign));
+    }
+
+    #[test]
+    fn div_zero() { assert_eq!(Percentage::new(13.0).checked_div(Percentage::new(0.0)), Err(UnitError::DivisionByZero))}
+
+    #[test]
+    fn checked() {
+        assert_eq!(Percentage::new(15.0).checked_add(Percentage::new(22.0)), Ok(Percentage { value: 37.0}));
+        assert_eq!(Percentage::new(f64::MAX).checked_mul(Percentage::new(2.0)), Err(UnitError::Overflow));
+        assert_eq!(Percentage::new(f64::NAN).checked_sub(Percentage::new(2.0)), Err(UnitError::NaN));
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(Decimal::try_from(Percentage::new(12.5)), Ok(Decimal::new(125, 1)));
+        assert_eq!(Decimal::try_from(Percentage::new(f64::NAN)), Err(UnitError::NaN));
+        assert_eq!(Decimal::try_from(Percentage::new(f64::INFINITY)), Err(UnitError::Overflow));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,263 @@
+use std::fmt;
+use std::ops::{Add, Sub, Mul};
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::error::{UnitError, checked_decimal};
+use super::money::Money;
+use super::rounding::RoundingPolicy;
+use crate::Percentage;
+
+/*
+    Basis points, per-mille and ratios
+
+    25bp = 0.25% = 2.5‰ = 0.0025
+
+    Values are decimals, so converting between these types is exact.
+    Conversions that multiply (‰ -> bp, ratio -> bp or ‰) can overflow
+    and are TryFrom. A Percentage converts through its shortest
+    representation: 12.34% is 1234bp and 1234bp is 12.34% again, a NaN
+    or infinite percentage is an error.
+    Like Percentage, they apply to Money with + - *.
+*/
+
+// 1bp = 0.01%
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct BasisPoints {
+    pub value: Decimal
+}
+
+// 1‰ = 0.1%
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct PerMille {
+    pub value: Decimal
+}
+
+// 1 = 100%
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Ratio {
+    pub value: Decimal
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParseRatioError {
+    Empty,
+    // i.e "25" for basis points
+    MissingUnit(&'static str),
+    InvalidNumber(String)
+}
+
+impl fmt::Display for ParseRatioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRatioError::Empty => write!(f, "empty input"),
+            ParseRatioError::MissingUnit(unit) => write!(f, "missing unit {:?}", unit),
+            ParseRatioError::InvalidNumber(n) => write!(f, "invalid number {:?}", n),
+        }
+    }
+}
+
+impl std::error::Error for ParseRatioError {}
+
+// Number with one of the `units` after it, spaces allowed in between
+fn parse_value(s: &str, units: &[&'static str]) -> Result<Decimal, ParseRatioError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(ParseRatioError::Empty);
+    }
+
+    let number = match units.iter().find_map(|unit| s.strip_suffix(unit)) {
+        Some(number) => number.trim_end(),
+        None if units.is_empty() => s,
+        None => return Err(ParseRatioError::MissingUnit(units[0])),
+    };
+
+    Decimal::from_str(number).map_err(|_| ParseRatioError::InvalidNumber(number.to_owned()))
+}
+
+macro_rules! impl_ratio_type {
+    ($name:ident $per_one:literal [$($unit:literal),*]) => {
+
+        impl $name {
+            pub fn new(value: Decimal) -> $name {
+                $name { value }
+            }
+
+            // 25bp -> 0.0025
+            pub fn rate(self) -> Decimal {
+                self.value / Decimal::from($per_one)
+            }
+
+            pub fn checked_from_rate(rate: Decimal) -> Result<$name, UnitError> {
+                Ok($name::new(rate.checked_mul(Decimal::from($per_one)).ok_or(UnitError::Overflow)?))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseRatioError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok($name::new(parse_value(s, &[$($unit),*])?))
+            }
+        }
+
+        impl TryFrom<Percentage> for $name {
+            type Error = UnitError;
+
+            fn try_from(percentage: Percentage) -> Result<$name, UnitError> {
+                $name::checked_from_rate(checked_decimal(percentage.value)? / Decimal::ONE_HUNDRED)
+            }
+        }
+
+        impl TryFrom<$name> for Percentage {
+            type Error = UnitError;
+
+            fn try_from(value: $name) -> Result<Percentage, UnitError> {
+                let percent = value.rate().checked_mul(Decimal::ONE_HUNDRED).ok_or(UnitError::Overflow)?;
+                Ok(Percentage::new(percent.to_f64().ok_or(UnitError::Overflow)?))
+            }
+        }
+
+        // results are rounded with the default policy, like with a Percentage
+        impl Add<$name> for Money {
+            type Output = Money;
+
+            fn add(self, rhs: $name) -> Self::Output {
+                Money::new(self.amount + self.amount * rhs.rate(), self.currency).round(RoundingPolicy::default())
+            }
+        }
+
+        impl Sub<$name> for Money {
+            type Output = Money;
+
+            fn sub(self, rhs: $name) -> Self::Output {
+                Money::new(self.amount - self.amount * rhs.rate(), self.currency).round(RoundingPolicy::default())
+            }
+        }
+
+        impl Mul<$name> for Money {
+            type Output = Money;
+
+            fn mul(self, rhs: $name) -> Self::Output {
+                Money::new(self.amount * rhs.rate(), self.currency).round(RoundingPolicy::default())
+            }
+        }
+    };
+}
+
+impl_ratio_type!(BasisPoints 10000 ["bps", "bp"]);
+impl_ratio_type!(PerMille 1000 ["‰"]);
+impl_ratio_type!(Ratio 1 []);
+
+// Smaller numbers never overflow, larger ones can
+macro_rules! impl_ratio_conversion {
+    ($from:ident => $to:ident / $factor:literal) => {
+        impl From<$from> for $to {
+            fn from(value: $from) -> $to {
+                $to::new(value.value / Decimal::from($factor))
+            }
+        }
+    };
+    ($from:ident => $to:ident * $factor:literal) => {
+        impl TryFrom<$from> for $to {
+            type Error = UnitError;
+
+            fn try_from(value: $from) -> Result<$to, UnitError> {
+                Ok($to::new(value.value.checked_mul(Decimal::from($factor)).ok_or(UnitError::Overflow)?))
+            }
+        }
+    };
+}
+
+impl_ratio_conversion!(BasisPoints => PerMille / 10);
+impl_ratio_conversion!(BasisPoints => Ratio / 10000);
+impl_ratio_conversion!(PerMille => Ratio / 1000);
+impl_ratio_conversion!(PerMille => BasisPoints * 10);
+impl_ratio_conversion!(Ratio => BasisPoints * 10000);
+impl_ratio_conversion!(Ratio => PerMille * 1000);
+
+impl fmt::Display for BasisPoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}bp", self.value.normalize())
+    }
+}
+
+impl fmt::Display for PerMille {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}‰", self.value.normalize())
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value.normalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::testing::eur;
+
+    #[test]
+    fn conversions() {
+        let bp = BasisPoints::new(dec!(25));
+        assert_eq!(PerMille::from(bp), PerMille::new(dec!(2.5)));
+        assert_eq!(Ratio::from(bp), Ratio::new(dec!(0.0025)));
+        assert_eq!(Percentage::try_from(bp), Ok(Percentage::new(0.25)));
+        assert_eq!(BasisPoints::try_from(Ratio::from(bp)), Ok(bp));
+        assert_eq!(PerMille::try_from(Ratio::new(dec!(0.003))), Ok(PerMille::new(dec!(3))));
+
+        // round trips through Percentage are exact
+        for value in [0.07, 12.34, -3.0, 0.001, 99.99] {
+            let percentage = Percentage::new(value);
+            assert_eq!(BasisPoints::try_from(percentage).and_then(Percentage::try_from), Ok(percentage));
+            assert_eq!(PerMille::try_from(percentage).and_then(Percentage::try_from), Ok(percentage));
+            assert_eq!(Ratio::try_from(percentage).and_then(Percentage::try_from), Ok(percentage));
+        }
+        assert_eq!(BasisPoints::try_from(Percentage::new(12.34)), Ok(BasisPoints::new(dec!(1234))));
+    }
+
+    #[test]
+    fn invalid_conversions() {
+        assert_eq!(BasisPoints::try_from(Percentage::new(f64::NAN)), Err(UnitError::NaN));
+        assert_eq!(PerMille::try_from(Percentage::new(f64::INFINITY)), Err(UnitError::Overflow));
+        assert_eq!(Ratio::try_from(Percentage::new(1e300)), Err(UnitError::Overflow));
+        assert_eq!(BasisPoints::try_from(Ratio::new(Decimal::MAX)), Err(UnitError::Overflow));
+        assert_eq!(Percentage::try_from(Ratio::new(Decimal::MAX)), Err(UnitError::Overflow));
+        assert_eq!(BasisPoints::checked_from_rate(Decimal::MAX), Err(UnitError::Overflow));
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(BasisPoints::from_str("25bp"), Ok(BasisPoints::new(dec!(25))));
+        assert_eq!(BasisPoints::from_str(" 12.5 bps"), Ok(BasisPoints::new(dec!(12.5))));
+        assert_eq!(PerMille::from_str("3‰"), Ok(PerMille::new(dec!(3))));
+        assert_eq!(PerMille::from_str("-0.5 ‰"), Ok(PerMille::new(dec!(-0.5))));
+        assert_eq!(Ratio::from_str("0.25"), Ok(Ratio::new(dec!(0.25))));
+
+        assert_eq!(BasisPoints::from_str("25"), Err(ParseRatioError::MissingUnit("bps")));
+        assert_eq!(PerMille::from_str("x‰"), Err(ParseRatioError::InvalidNumber("x".to_owned())));
+        assert_eq!(Ratio::from_str(" "), Err(ParseRatioError::Empty));
+
+        assert_eq!(BasisPoints::new(dec!(25.0)).to_string(), "25bp");
+        assert_eq!(PerMille::new(dec!(3)).to_string(), "3‰");
+        assert_eq!(Ratio::new(dec!(0.2500)).to_string(), "0.25");
+        assert_eq!(Ratio::from_str(&Ratio::new(dec!(0.25)).to_string()), Ok(Ratio::new(dec!(0.25))));
+    }
+
+    #[test]
+    fn money() {
+        assert_eq!(eur(dec!(1000)) * BasisPoints::new(dec!(25)), eur(dec!(2.5)));
+        assert_eq!(eur(dec!(1000)) + BasisPoints::new(dec!(25)), eur(dec!(1002.5)));
+        assert_eq!(eur(dec!(1000)) - PerMille::new(dec!(3)), eur(dec!(997)));
+        assert_eq!(eur(dec!(42)) * Ratio::new(dec!(0.5)), eur(dec!(21)));
+
+        // same result as the equivalent Percentage
+        let price = eur(dec!(19.99));
+        assert_eq!(price + BasisPoints::new(dec!(1250)), price + Percentage::new(12.5));
+        assert_eq!(price - PerMille::new(dec!(125)), price - Percentage::new(12.5));
+    }
+}
@@ -1,121 +1,253 @@
 use std::fmt;
 use std::ops::{Add, Sub, Mul, Div};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 
 use crate::Money;
 use crate::Percentage;
+use super::error::{UnitError, checked_decimal, finite};
+use super::moneybag::MoneyBag;
+use super::rounding::RoundingPolicy;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum ResType {
     Int(i32),
     Float(f64),
     Money(Money),
-    Percent(Percentage)
+    Percent(Percentage),
+    // Amounts in several currencies
+    Bag(MoneyBag)
 }
 
-impl ResType {
-    fn is_money(self) -> bool {
-        matches!(self, ResType::Money(_))
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Op {
+    fn decimal(self, a: Decimal, b: Decimal) -> Result<Decimal, UnitError> {
+        let result = match self {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div if b.is_zero() => return Err(UnitError::DivisionByZero),
+            Op::Div => a.checked_div(b),
+        };
+        result.ok_or(UnitError::Overflow)
     }
 
-    fn money(self) -> Option<Money> {
-        if let ResType::Money(money) = self {
-            return Some(money)
+    fn float(self, a: f64, b: f64) -> Result<f64, UnitError> {
+        match self {
+            Op::Add => finite(a + b),
+            Op::Sub => finite(a - b),
+            Op::Mul => finite(a * b),
+            Op::Div if b == 0.0 => Err(UnitError::DivisionByZero),
+            Op::Div => finite(a / b),
         }
-        None
-    }
-
-    fn is_float(self) -> bool {
-        matches!(self, ResType::Float(_))
-    }
-
-    fn is_int(self) -> bool {
-        matches!(self, ResType::Int(_))
-    }
-
-    fn is_percentage(self) -> bool {
-        matches!(self, ResType::Percent(_))
-    }
-
-    // fn arithmetic_operation<F, T, U>(self, rhs: ResType, operation: F) -> ResType
-    // where
-    //     F: Fn(T, U) -> T,
-    // {
-    //     // We have some money, the result will be money
-    //     if self.is_money() || rhs.is_money() {
-    //         let (money, other) = match (self, rhs) {
-    //             (a, other) if a.is_money() => (a, other),
-    //             (a, other) if other.is_money() => (other, a),
-    //             (_, _) => unreachable!(),
-    //         };
-
-    //         let x: Money = money.money().unwrap();
-
-    //         match other {
-    //             ResType::Percent(p) => {
-    //                 return ResType::Money(operation(x, p))
-    //             },
-    //             ResType::Float(f) => {
-    //                 return ResType::Money(x + f);
-    //             },
-    //             ResType::Money(m) => {
-    //                 return ResType::Money(x + m);
-    //             },
-    //             ResType::Int(i) => {
-    //                 return ResType::Money(x + i);
-    //             },
-    //         }
-    //     }
-        
-
-    //     // We have some float, the result will be a float
-    //     if self.is_float() || rhs.is_float() {
-    //         let (float, other) = match (self, rhs) {
-    //             (a, other) if a.is_float() => (a, other),
-    //             (a, other) if other.is_float() => (other, a),
-    //             (_, _) => unreachable!(),
-    //         };
-
-    //         let x: f64 = float.into();
-
-    //         match other {
-    //             ResType::Percent(p) => {
-    //                 return ResType::Float(x + p)
-    //             },
-    //             ResType::Float(f) => {
-    //                 return ResType::Float(x + f);
-    //             },
-    //             ResType::Int(i) => {
-    //                 return ResType::Float(x + f64::from(i));
-    //             },
-    //             ResType::Money(_) => {
-    //                 unreachable!("Money should have been catch by the previous code.")
-    //             },
-    //         }
-    //     }
-        
-    //     todo!()
-
-    // }
+    }
+
+    fn int(self, a: i32, b: i32) -> Result<i32, UnitError> {
+        let result = match self {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div if b == 0 => return Err(UnitError::DivisionByZero),
+            Op::Div => a.checked_div(b),
+        };
+        result.ok_or(UnitError::Overflow)
+    }
 }
 
-impl Into<f64> for ResType {
-    fn into(self) -> f64 {
+impl ResType {
+    pub fn checked_add(self, rhs: ResType) -> Result<ResType, UnitError> {
+        self.checked_op(Op::Add, rhs)
+    }
+
+    pub fn checked_sub(self, rhs: ResType) -> Result<ResType, UnitError> {
+        self.checked_op(Op::Sub, rhs)
+    }
+
+    pub fn checked_mul(self, rhs: ResType) -> Result<ResType, UnitError> {
+        self.checked_op(Op::Mul, rhs)
+    }
+
+    pub fn checked_div(self, rhs: ResType) -> Result<ResType, UnitError> {
+        self.checked_op(Op::Div, rhs)
+    }
+
+    fn is_bag(&self) -> bool {
+        matches!(self, ResType::Bag(_))
+    }
+
+    // Two amounts in different currencies
+    fn mixes_currencies(&self, other: &ResType) -> bool {
+        matches!((self, other), (ResType::Money(a), ResType::Money(b)) if a.currency != b.currency)
+    }
+
+    fn into_bag(self) -> Option<MoneyBag> {
         match self {
-            ResType::Float(f) => f,
-            ResType::Int(i) => i as f64,
-            ResType::Money(m) => m.into(),
-            ResType::Percent(p) => p.into()
+            ResType::Money(money) => Some(MoneyBag::from(money)),
+            ResType::Bag(bag) => Some(bag),
+            _ => None
         }
     }
-}
 
-impl Into<i32> for ResType {
-    fn into(self) -> i32 {
+    pub(crate) fn number(&self) -> Option<f64> {
         match self {
-            ResType::Float(f) => f as i32,
-            ResType::Int(i) => i,
-            ResType::Money(m) => m.into(),
-            ResType::Percent(p) => p.into()
+            ResType::Int(i) => Some(f64::from(*i)),
+            ResType::Float(f) => Some(*f),
+            _ => None
+        }
+    }
+
+    fn checked_op(self, op: Op, rhs: ResType) -> Result<ResType, UnitError> {
+        let incompatible = UnitError::IncompatibleUnits(format!("{} {} {}", self, op, rhs));
+
+        // Several currencies, the result will be a bag
+        if self.is_bag() || rhs.is_bag() || self.mixes_currencies(&rhs) {
+            return bag_op(self, op, rhs, incompatible).map(ResType::Bag);
+        }
+
+        match (self, rhs) {
+            // We have some money, the result will be money
+            (ResType::Money(a), ResType::Money(b)) => match op {
+                Op::Add => Ok(ResType::Money(a.checked_add(b)?)),
+                Op::Sub => Ok(ResType::Money(a.checked_sub(b)?)),
+                // 100€ / 2€ = 50, no unit left
+                Op::Div => Ok(ResType::Float(a.checked_div_money(b)?.to_f64().ok_or(UnitError::Overflow)?)),
+                // 300€ * 2€ has no meaning
+                Op::Mul => Err(incompatible),
+            },
+            (ResType::Money(m), ResType::Percent(p)) => Ok(ResType::Money(money_percentage(m, op, p)?)),
+            (ResType::Percent(p), ResType::Money(m)) => match op {
+                Op::Add | Op::Mul => Ok(ResType::Money(money_percentage(m, op, p)?)),
+                Op::Sub | Op::Div => Err(incompatible),
+            },
+            (ResType::Money(m), other) => {
+                let x = checked_decimal(other.number().ok_or(incompatible)?)?;
+                Ok(ResType::Money(Money::new(op.decimal(m.amount, x)?, m.currency)))
+            },
+            (other, ResType::Money(m)) => match op {
+                Op::Div => Err(incompatible),
+                _ => {
+                    let x = checked_decimal(other.number().ok_or(incompatible)?)?;
+                    Ok(ResType::Money(Money::new(op.decimal(x, m.amount)?, m.currency)))
+                },
+            },
+
+            // Our percent type
+            (ResType::Percent(a), ResType::Percent(b)) => match op {
+                Op::Add => Ok(ResType::Percent(a.checked_add(b)?)),
+                Op::Sub => Ok(ResType::Percent(a.checked_sub(b)?)),
+                Op::Mul => Ok(ResType::Percent(a.checked_mul(b)?)),
+                // 13% / 2% = 6.5
+                Op::Div => Ok(ResType::Float(a.checked_div(b)?)),
+            },
+            (number, ResType::Percent(p)) => {
+                let x = number.number().ok_or(incompatible)?;
+                Ok(ResType::Float(number_percentage(x, op, p)?))
+            },
+            (ResType::Percent(p), number) => {
+                let x = number.number().ok_or(incompatible.clone())?;
+                match op {
+                    Op::Add | Op::Mul => Ok(ResType::Float(number_percentage(x, op, p)?)),
+                    Op::Div => Ok(ResType::Percent(Percentage::new(op.float(p.value, x)?))),
+                    Op::Sub => Err(incompatible),
+                }
+            },
+
+            // Same thing for the int type
+            (ResType::Int(a), ResType::Int(b)) => Ok(ResType::Int(op.int(a, b)?)),
+
+            // Finally floats
+            (a, b) => match (a.number(), b.number()) {
+                (Some(a), Some(b)) => Ok(ResType::Float(op.float(a, b)?)),
+                _ => Err(incompatible),
+            },
+        }
+    }
+}
+
+// 42€ + 12%, rounded like the Money operators
+fn money_percentage(money: Money, op: Op, percentage: Percentage) -> Result<Money, UnitError> {
+    let amount = match op {
+        Op::Add | Op::Sub => op.decimal(money.amount, money.checked_percentage_part(percentage)?)?,
+        Op::Mul => money.checked_percentage_part(percentage)?,
+        Op::Div => op.decimal(money.amount, checked_decimal(percentage.value)? / Decimal::ONE_HUNDRED)?,
+    };
+    Ok(Money::new(amount, money.currency).round(RoundingPolicy::default()))
+}
+
+// 12 + 11% = 13.32
+fn number_percentage(x: f64, op: Op, percentage: Percentage) -> Result<f64, UnitError> {
+    let part = finite(x * percentage.value / 100.0)?;
+    match op {
+        Op::Add | Op::Sub => op.float(x, part),
+        Op::Mul => Ok(part),
+        Op::Div => op.float(x, percentage.value / 100.0),
+    }
+}
+
+// Only amounts and percentages can be mixed with a bag
+fn bag_op(a: ResType, op: Op, b: ResType, incompatible: UnitError) -> Result<MoneyBag, UnitError> {
+    match (a, op, b) {
+        (ResType::Bag(bag), Op::Add, ResType::Percent(p)) | (ResType::Percent(p), Op::Add, ResType::Bag(bag)) => bag.checked_add_percentage(p),
+        (ResType::Bag(bag), Op::Mul, ResType::Percent(p)) | (ResType::Percent(p), Op::Mul, ResType::Bag(bag)) => bag.checked_mul_percentage(p),
+        (ResType::Bag(bag), Op::Sub, ResType::Percent(p)) => bag.checked_sub_percentage(p),
+        (a, Op::Add, b) => {
+            let (a, b) = a.into_bag().zip(b.into_bag()).ok_or(incompatible)?;
+            a.checked_add(b)
+        },
+        (a, Op::Sub, b) => {
+            let (a, b) = a.into_bag().zip(b.into_bag()).ok_or(incompatible)?;
+            a.checked_sub(b)
+        },
+        _ => Err(incompatible)
+    }
+}
+
+// A bag has no single value, collapse it first
+impl TryFrom<ResType> for f64 {
+    type Error = UnitError;
+
+    fn try_from(res: ResType) -> Result<f64, UnitError> {
+        match res {
+            ResType::Float(f) => Ok(f),
+            ResType::Int(i) => Ok(i as f64),
+            ResType::Money(m) => Ok(m.into()),
+            ResType::Percent(p) => Ok(p.into()),
+            ResType::Bag(bag) => Err(UnitError::IncompatibleUnits(format!("{} as a number", bag)))
+        }
+    }
+}
+
+impl TryFrom<ResType> for i32 {
+    type Error = UnitError;
+
+    fn try_from(res: ResType) -> Result<i32, UnitError> {
+        match res {
+            ResType::Float(f) => Ok(f as i32),
+            ResType::Int(i) => Ok(i),
+            ResType::Money(m) => Ok(m.into()),
+            ResType::Percent(p) => Ok(p.into()),
+            ResType::Bag(bag) => Err(UnitError::IncompatibleUnits(format!("{} as a number", bag)))
         }
     }
 }
@@ -127,114 +259,152 @@ impl fmt::Display for ResType {
             ResType::Float(float) => write!(f, "{}", float),
             ResType::Money(m) =>  write!(f, "{}", m),
             ResType::Percent(p) =>  write!(f, "{}", p),
+            ResType::Bag(bag) =>  write!(f, "{}", bag),
         }
     }
 }
 
+/*
+    The operators panic where the checked_* functions return an error
+*/
 macro_rules! impl_arithmetic_op_for_ResType {
-    ($trait_name:ident $fn_name:ident $op:tt) => {
+    ($trait_name:ident $fn_name:ident $checked_fn:ident) => {
 
         impl $trait_name <ResType> for ResType {
             type Output = ResType;
 
             fn $fn_name (self, rhs: ResType) -> Self::Output {
-                
-                // We have some money, the result will be money
-                if self.is_money() || rhs.is_money() {
-                    let (money, other) = match (self, rhs) {
-                        (a, other) if a.is_money() => (a, other),
-                        (a, other) if other.is_money() => (other, a),
-                        (_, _) => unreachable!(),
-                    };
-
-                    let x: Money = money.money().unwrap();
-
-                    match other {
-                        ResType::Percent(p) => {
-                            return ResType::Money(x $op p)
-                        },
-                        ResType::Float(f) => {
-                            return ResType::Money(x $op f);
-                        },
-                        ResType::Money(m) => {
-                            return ResType::Money(x $op m);
-                        },
-                        ResType::Int(i) => {
-                            return ResType::Money(x $op i);
-                        },
-                    }
-                }
-                
-
-                // We have some float, the result will be a float
-                if self.is_float() || rhs.is_float() {
-                    let (float, other) = match (self, rhs) {
-                        (a, other) if a.is_float() => (a, other),
-                        (a, other) if other.is_float() => (other, a),
-                        (_, _) => unreachable!(),
-                    };
-
-                    let x: f64 = float.into();
-
-                    match other {
-                        ResType::Percent(p) => {
-                            return ResType::Float(x $op p)
-                        },
-                        ResType::Float(f) => {
-                            return ResType::Float(x $op f);
-                        },
-                        ResType::Int(i) => {
-                            return ResType::Float(x $op f64::from(i));
-                        },
-                        ResType::Money(_) => {
-                            unreachable!("Money should have been catch by the previous code.")
-                        },
-                    }
-                }
-                
-                // Same thing for the int type
-                if self.is_int() || rhs.is_int() {
-                    let (int, other) = match (self, rhs) {
-                        (a, other) if a.is_int() => (a, other),
-                        (a, other) if other.is_int() => (other, a),
-                        (_, _) => unreachable!(),
-                    };
-
-                    let x: i32 = int.into();
-
-                    match other {
-                        ResType::Percent(p) => {
-                            return ResType::Float(x $op p)
-                        },
-                        ResType::Int(i) => {
-                            return ResType::Int(x $op i);
-                        },
-                        _ => unreachable!("Other types should have been catch by the previously."),
-                    }
-                }
-                
-
-                // Finally our percent type
-                if self.is_percentage() && rhs.is_percentage() {
-
-                    if let (
-                        ResType::Percent(p1),
-                        ResType::Percent(p2)
-                    ) = (self, rhs) {
-                        return ResType::Percent(p1 $op p2)
-                    }
-                    
-                    unreachable!("By construction, we should have 2 percentage type.")
-                }
-                
-
-                unreachable!("All type should have be cratch by now")
+                self.$checked_fn(rhs).unwrap_or_else(|e| panic!("{}", e))
             }
         }
     }
 }
 
-impl_arithmetic_op_for_ResType!(Add add +);
-impl_arithmetic_op_for_ResType!(Sub sub +);
-impl_arithmetic_op_for_ResType!(Mul mul +);
-impl_arithmetic_op_for_ResType!(Div div +);
+impl_arithmetic_op_for_ResType!(Add add checked_add);
+impl_arithmetic_op_for_ResType!(Sub sub checked_sub);
+impl_arithmetic_op_for_ResType!(Mul mul checked_mul);
+impl_arithmetic_op_for_ResType!(Div div checked_div);
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::currency::Currency;
+
+    #[test]
+    fn mixed_currencies_make_a_bag() {
+        let eur = ResType::Money(Money::new(dec!(10), Currency::EUR));
+        let usd = ResType::Money(Money::new(dec!(5), Currency::USD));
+
+        let bag = MoneyBag::from(Money::new(dec!(10), Currency::EUR)) + Money::new(dec!(5), Currency::USD);
+        match eur.clone() + usd {
+            ResType::Bag(b) => assert_eq!(b, bag),
+            other => panic!("expected a bag, got {}", other),
+        }
+
+        let total = ResType::Bag(bag) + eur;
+        assert_eq!(total.to_string(), "20.00€ + $5.00");
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(ResType::Int(7).checked_sub(ResType::Int(2)), Ok(ResType::Int(5)));
+        assert_eq!(ResType::Int(7).checked_div(ResType::Int(0)), Err(UnitError::DivisionByZero));
+        assert_eq!(ResType::Int(i32::MAX).checked_add(ResType::Int(1)), Err(UnitError::Overflow));
+        assert_eq!(ResType::Float(1.0).checked_div(ResType::Float(0.0)), Err(UnitError::DivisionByZero));
+        assert_eq!(ResType::Float(f64::NAN).checked_mul(ResType::Int(2)), Err(UnitError::NaN));
+        assert_eq!(
+            ResType::Percent(Percentage::new(13.0)).checked_div(ResType::Percent(Percentage::new(0.0))),
+            Err(UnitError::DivisionByZero)
+        );
+        assert_eq!(
+            ResType::Percent(Percentage::new(50.0)).checked_mul(ResType::Percent(Percentage::new(10.0))),
+            Ok(ResType::Percent(Percentage::new(5.0)))
+        );
+        assert_eq!(
+            ResType::Percent(Percentage::new(13.0)).checked_div(ResType::Percent(Percentage::new(2.0))),
+            Ok(ResType::Float(6.5))
+        );
+    }
+
+    #[test]
+    fn checked_money() {
+        let money = ResType::Money(Money::new(dec!(42), Currency::EUR));
+        assert_eq!(money.clone().checked_sub(ResType::Int(2)), Ok(ResType::Money(Money::new(dec!(40), Currency::EUR))));
+        assert_eq!(ResType::Int(50).checked_sub(money.clone()), Ok(ResType::Money(Money::new(dec!(8), Currency::EUR))));
+        assert_eq!(money.clone().checked_div(ResType::Float(0.0)), Err(UnitError::DivisionByZero));
+        assert_eq!(
+            ResType::Int(2).checked_div(money.clone()),
+            Err(UnitError::IncompatibleUnits("2 / 42.00€".to_owned()))
+        );
+        assert_eq!(
+            ResType::Percent(Percentage::new(10.0)).checked_sub(money),
+            Err(UnitError::IncompatibleUnits("10.0% - 42.00€".to_owned()))
+        );
+    }
+
+    #[test]
+    fn dimensions() {
+        let a = ResType::Money(Money::new(dec!(100), Currency::EUR));
+        let b = ResType::Money(Money::new(dec!(2), Currency::EUR));
+
+        assert_eq!(a.clone().checked_div(b.clone()), Ok(ResType::Float(50.0)));
+        assert_eq!(a.clone().checked_div(ResType::Int(2)), Ok(ResType::Money(Money::new(dec!(50), Currency::EUR))));
+        assert_eq!(a.clone().checked_mul(b), Err(UnitError::IncompatibleUnits("100.00€ * 2.00€".to_owned())));
+        assert_eq!(
+            a.checked_div(ResType::Money(Money::new(dec!(2), Currency::USD))),
+            Err(UnitError::IncompatibleUnits("100.00€ / $2.00".to_owned()))
+        );
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(ResType::Int(12).checked_add(ResType::Percent(Percentage::new(11.0))), Ok(ResType::Float(13.32)));
+        assert_eq!(ResType::Int(200).checked_sub(ResType::Percent(Percentage::new(10.0))), Ok(ResType::Float(180.0)));
+        assert_eq!(
+            ResType::Money(Money::new(dec!(42), Currency::EUR)) - ResType::Percent(Percentage::new(50.0)),
+            ResType::Money(Money::new(dec!(21), Currency::EUR))
+        );
+    }
+
+    #[test]
+    fn bag_errors() {
+        let bag = ResType::Money(Money::new(dec!(10), Currency::EUR)) + ResType::Money(Money::new(dec!(5), Currency::USD));
+        assert_eq!(
+            bag.clone().checked_add(ResType::Int(1)),
+            Err(UnitError::IncompatibleUnits("10.00€ + $5.00 + 1".to_owned()))
+        );
+        assert_eq!(bag.clone().checked_mul(bag.clone()), Err(UnitError::IncompatibleUnits("10.00€ + $5.00 * 10.00€ + $5.00".to_owned())));
+        assert_eq!(bag.clone().checked_add(ResType::Percent(Percentage::new(f64::NAN))), Err(UnitError::NaN));
+        assert_eq!(bag.checked_mul(ResType::Percent(Percentage::new(f64::INFINITY))), Err(UnitError::Overflow));
+
+        let full = ResType::Money(Money::new(Decimal::MAX, Currency::EUR)) + ResType::Money(Money::new(dec!(5), Currency::USD));
+        assert_eq!(full.clone().checked_add(full.clone()), Err(UnitError::Overflow));
+        assert_eq!(full.clone().checked_add(ResType::Money(Money::new(dec!(1), Currency::EUR))), Err(UnitError::Overflow));
+        assert_eq!(full.checked_add(ResType::Percent(Percentage::new(10.0))), Err(UnitError::Overflow));
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn operator_panics() {
+        let _ = ResType::Int(1) / ResType::Int(0);
+    }
+
+    #[test]
+    fn bag_with_percentage() {
+        let bag = ResType::Bag(MoneyBag::from(Money::new(dec!(10), Currency::EUR)) + Money::new(dec!(5), Currency::USD));
+        assert_eq!((bag + ResType::Percent(Percentage::new(10.0))).to_string(), "11.00€ + $5.50");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(f64::try_from(ResType::Percent(Percentage::new(12.5))), Ok(12.5));
+        assert_eq!(f64::try_from(ResType::Money(Money::new(dec!(4.25), Currency::EUR))), Ok(4.25));
+        assert_eq!(i32::try_from(ResType::Float(3.7)), Ok(3));
+
+        let bag = MoneyBag::from(Money::new(dec!(10), Currency::EUR)) + Money::new(dec!(5), Currency::USD);
+        assert!(matches!(f64::try_from(ResType::Bag(bag.clone())), Err(UnitError::IncompatibleUnits(_))));
+        assert!(matches!(i32::try_from(ResType::Bag(bag)), Err(UnitError::IncompatibleUnits(_))));
+    }
+}
@@ -0,0 +1,106 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum RoundingMode {
+    // 2.345 -> 2.35, -2.345 -> -2.35
+    #[default]
+    HalfUp,
+    // banker's rounding: 2.345 -> 2.34, 2.355 -> 2.36
+    HalfEven,
+    TowardZero,
+    Ceiling,
+    Floor
+}
+
+impl From<RoundingMode> for RoundingStrategy {
+    fn from(mode: RoundingMode) -> RoundingStrategy {
+        match mode {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::TowardZero => RoundingStrategy::ToZero,
+            RoundingMode::Ceiling => RoundingStrategy::ToPositiveInfinity,
+            RoundingMode::Floor => RoundingStrategy::ToNegativeInfinity,
+        }
+    }
+}
+
+/*
+    Rounding Policy
+
+    How and to what step a result is rounded. Without an increment
+    amounts are rounded to the minor unit of their currency (cents),
+    cash rounding uses a bigger one: 0.05 for CHF.
+
+    Money is rounded after a Percentage is applied and after a
+    conversion, or on demand with `Money::round`.
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    pub increment: Option<Decimal>
+}
+
+impl RoundingPolicy {
+    pub fn new(mode: RoundingMode) -> RoundingPolicy {
+        RoundingPolicy { mode, increment: None }
+    }
+
+    // Round to the nearest multiple of `increment`, i.e 0.05
+    pub fn cash(increment: Decimal) -> RoundingPolicy {
+        RoundingPolicy { mode: RoundingMode::HalfUp, increment: Some(increment) }
+    }
+
+    pub fn with_increment(self, increment: Decimal) -> RoundingPolicy {
+        RoundingPolicy { increment: Some(increment), ..self }
+    }
+
+    pub fn round(&self, amount: Decimal, minor_units: u8) -> Decimal {
+        match self.increment {
+            // a multiple too large for a Decimal: the increment is below
+            // the precision of the amount, which stays as it is
+            Some(increment) if !increment.is_zero() => amount.checked_div(increment)
+                .and_then(|steps| steps.round_dp_with_strategy(0, self.mode.into()).checked_mul(increment))
+                .unwrap_or(amount),
+            _ => amount.round_dp_with_strategy(u32::from(minor_units), self.mode.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn round(mode: RoundingMode, amount: Decimal) -> Decimal {
+        RoundingPolicy::new(mode).round(amount, 2)
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(round(RoundingMode::HalfUp, dec!(2.345)), dec!(2.35));
+        assert_eq!(round(RoundingMode::HalfUp, dec!(-2.345)), dec!(-2.35));
+        assert_eq!(round(RoundingMode::HalfEven, dec!(2.345)), dec!(2.34));
+        assert_eq!(round(RoundingMode::HalfEven, dec!(2.355)), dec!(2.36));
+        assert_eq!(round(RoundingMode::TowardZero, dec!(-2.349)), dec!(-2.34));
+        assert_eq!(round(RoundingMode::Ceiling, dec!(2.341)), dec!(2.35));
+        assert_eq!(round(RoundingMode::Floor, dec!(-2.341)), dec!(-2.35));
+    }
+
+    #[test]
+    fn cash() {
+        let chf = RoundingPolicy::cash(dec!(0.05));
+        assert_eq!(chf.round(dec!(12.33), 2), dec!(12.35));
+        assert_eq!(chf.round(dec!(12.32), 2), dec!(12.30));
+        assert_eq!(chf.round(dec!(12.325), 2), dec!(12.35));
+
+        let tiny = RoundingPolicy::cash(dec!(0.0000000000000000000000000001));
+        assert_eq!(tiny.round(dec!(1000.25), 2), dec!(1000.25));
+        assert_eq!(chf.round(Decimal::MAX, 2), Decimal::MAX);
+    }
+
+    #[test]
+    fn minor_units() {
+        assert_eq!(RoundingPolicy::default().round(dec!(1234.5), 0), dec!(1235));
+        assert_eq!(RoundingPolicy::default().round(dec!(1.2345), 3), dec!(1.235));
+    }
+}
@@ -0,0 +1,165 @@
+/*
+    Serde support, behind the `serde` feature
+
+    Human readable and stable:
+    Currency    "EUR"
+    Money       {"amount":"42.00","currency":"EUR"}
+    Percentage  "12.5%"
+    MoneyBag    [{"amount":"10.00","currency":"EUR"},{"amount":"5.00","currency":"USD"}]
+    ResType     {"type":"money","value":{"amount":"42.00","currency":"EUR"}}
+
+    Amounts are strings so no precision is lost on the way.
+*/
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+
+use super::currency::Currency;
+use super::money::Money;
+use super::moneybag::MoneyBag;
+use crate::Percentage;
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_code(&code).ok_or_else(|| D::Error::custom(format!("unknown currency {:?}", code)))
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // at least the minor units of the currency: 42 -> "42.00"
+        let mut amount = self.amount;
+        if amount.scale() < u32::from(self.currency.minor_units) {
+            amount.rescale(u32::from(self.currency.minor_units));
+        }
+
+        let mut state = serializer.serialize_struct("Money", 2)?;
+        state.serialize_field("amount", &amount.to_string())?;
+        state.serialize_field("currency", &self.currency)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Money", deny_unknown_fields)]
+struct MoneyRepr {
+    amount: String,
+    currency: Currency
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MoneyRepr::deserialize(deserializer)?;
+        let amount = Decimal::from_str(&repr.amount)
+            .map_err(|_| D::Error::custom(format!("invalid amount {:?}", repr.amount)))?;
+        Ok(Money::new(amount, repr.currency))
+    }
+}
+
+// "NaN%" or "inf%" would not read back, they are errors both ways
+impl Serialize for Percentage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.value.is_finite() {
+            return Err(serde::ser::Error::custom(format!("can't serialize {}%", self.value)));
+        }
+        serializer.serialize_str(&format!("{}%", self.value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Percentage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        // exactly what Serialize writes, no separators to guess
+        let value = s.trim().strip_suffix('%')
+            .and_then(|value| f64::from_str(value.trim()).ok())
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| D::Error::custom(format!("invalid percentage {:?}", s)))?;
+        Ok(Percentage::new(value))
+    }
+}
+
+impl Serialize for MoneyBag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for MoneyBag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Money>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::units::restype::ResType;
+
+    fn round_trip_json<T: Serialize + for<'de> Deserialize<'de> + PartialEq + std::fmt::Debug>(value: T, json: &str) {
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        assert_eq!(serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn json() {
+        round_trip_json(Currency::EUR, r#""EUR""#);
+        round_trip_json(Money::new(dec!(42), Currency::EUR), r#"{"amount":"42.00","currency":"EUR"}"#);
+        round_trip_json(Money::new(dec!(1234), Currency::JPY), r#"{"amount":"1234","currency":"JPY"}"#);
+        round_trip_json(Money::new(dec!(0.125), Currency::USD), r#"{"amount":"0.125","currency":"USD"}"#);
+        round_trip_json(Percentage::new(12.5), r#""12.5%""#);
+        round_trip_json(Percentage::new(12.345), r#""12.345%""#);
+        round_trip_json(Percentage::new(0.125), r#""0.125%""#);
+        round_trip_json(Percentage::new(-1.005), r#""-1.005%""#);
+    }
+
+    #[test]
+    fn restype_is_tagged() {
+        round_trip_json(ResType::Int(3), r#"{"type":"int","value":3}"#);
+        round_trip_json(ResType::Float(1.5), r#"{"type":"float","value":1.5}"#);
+        round_trip_json(ResType::Percent(Percentage::new(20.0)), r#"{"type":"percent","value":"20%"}"#);
+        round_trip_json(
+            ResType::Money(Money::new(dec!(42), Currency::EUR)),
+            r#"{"type":"money","value":{"amount":"42.00","currency":"EUR"}}"#
+        );
+        round_trip_json(
+            ResType::Bag(MoneyBag::from(Money::new(dec!(10), Currency::EUR)) + Money::new(dec!(5), Currency::USD)),
+            r#"{"type":"bag","value":[{"amount":"10.00","currency":"EUR"},{"amount":"5.00","currency":"USD"}]}"#
+        );
+    }
+
+    #[test]
+    fn toml() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Invoice {
+            vat: Percentage,
+            total: Money
+        }
+
+        let invoice = Invoice { vat: Percentage::new(20.0), total: Money::new(dec!(42), Currency::EUR) };
+        let text = toml::to_string(&invoice).unwrap();
+        assert_eq!(text, "vat = \"20%\"\n\n[total]\namount = \"42.00\"\ncurrency = \"EUR\"\n");
+        assert_eq!(toml::from_str::<Invoice>(&text).unwrap(), invoice);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(serde_json::from_str::<Currency>(r#""XYZ""#).is_err());
+        assert!(serde_json::from_str::<Money>(r#"{"amount":"12,5","currency":"EUR"}"#).is_err());
+        assert!(serde_json::from_str::<Percentage>(r#""12.5""#).is_err());
+        assert!(serde_json::from_str::<Percentage>(r#""NaN%""#).is_err());
+        assert!(serde_json::from_str::<Percentage>(r#""inf%""#).is_err());
+        assert!(serde_json::to_string(&Percentage::new(f64::NAN)).is_err());
+        assert!(serde_json::to_string(&Percentage::new(f64::NEG_INFINITY)).is_err());
+        assert!(serde_json::from_str::<Percentage>(r#""12,5 %""#).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+// Shorthands shared by the tests
+use rust_decimal::Decimal;
+
+use super::currency::Currency;
+use super::money::Money;
+
+pub(crate) fn eur(amount: Decimal) -> Money {
+    Money::new(amount, Currency::EUR)
+}
+
+pub(crate) fn usd(amount: Decimal) -> Money {
+    Money::new(amount, Currency::USD)
+}